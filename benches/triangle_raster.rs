@@ -0,0 +1,28 @@
+//! Misst den Rasterizer für das HSV-Dreieck: `cargo bench --bench triangle_raster`
+
+use rust_lamp::wheel_raster;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const MEASURE_TIME: Duration = Duration::from_secs(1);
+
+fn main() {
+    for size in [240, 500, 1000] {
+        // Gleiche Geometrie wie im ColorPicker: Ring 10 px breit, Dreieck direkt darin
+        let center = (size as f64 / 2.0, size as f64 / 2.0);
+        let radius = size as f64 / 2.0 - 20.0;
+        let points = wheel_raster::triangle_points(center, radius, 1.0);
+        let rect = wheel_raster::bounding_box(points);
+        let stride = rect.width as usize * wheel_raster::BYTES_PER_PIXEL;
+        let mut data = vec![0u8; stride * rect.height as usize];
+
+        let mut iterations = 0u32;
+        let start = Instant::now();
+        while start.elapsed() < MEASURE_TIME {
+            wheel_raster::rasterize_triangle(black_box(&mut data), stride, rect, black_box(points), (1.0, 0.5, 0.0));
+            iterations += 1;
+        }
+        let per_iteration = start.elapsed() / iterations;
+        println!("{0}x{0}: {1:?} pro Dreieck ({2} Durchläufe)", size, per_iteration, iterations);
+    }
+}
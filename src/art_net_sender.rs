@@ -0,0 +1,647 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const ART_NET_PORT: u16 = 6454;
+pub const ART_NET_ID: &[u8; 8] = b"Art-Net\0";
+pub const PROTOCOL_VERSION: u16 = 14;
+pub const DMX_CHANNELS: usize = 512;
+
+pub const OP_POLL: u16 = 0x2000;
+pub const OP_POLL_REPLY: u16 = 0x2100;
+pub const OP_DMX: u16 = 0x5000;
+pub const OP_SYNC: u16 = 0x5200;
+
+/// ArtPollReply ist bis einschließlich MAC-Adresse Pflicht, alles danach kam erst mit Art-Net 3/4 dazu.
+const POLL_REPLY_MIN_LENGTH: usize = 207;
+const POLL_REPLY_LENGTH: usize = 239;
+
+pub const STYLE_CONTROLLER: u8 = 0x01;
+/// PortTypes: Port kann Art-Net nach DMX512 ausgeben.
+pub const PORT_TYPE_OUTPUT: u8 = 0x80;
+/// GoodOutputA: auf dem Port werden Daten gesendet.
+pub const GOOD_OUTPUT_TRANSMITTING: u8 = 0x80;
+/// Status1: Anzeigen normal, Port-Adressen am Gerät eingestellt.
+pub const STATUS1_NORMAL: u8 = 0xD0;
+/// Status2: unterstützt 15-bit Port-Adressen (Art-Net 3/4).
+pub const STATUS2_PORT_ADDRESS_15_BIT: u8 = 0x08;
+
+/// 15-bit Port-Address eines Art-Net Universums: Net (7 bit), SubNet (4 bit), Universe (4 bit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PortAddress {
+    pub net: u8,
+    pub sub_net: u8,
+    pub universe: u8,
+}
+
+impl PortAddress {
+    pub fn new(net: u8, sub_net: u8, universe: u8) -> Self {
+        Self {
+            net: net & 0x7F,
+            sub_net: sub_net & 0x0F,
+            universe: universe & 0x0F,
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        ((self.net as u16 & 0x7F) << 8) | ((self.sub_net as u16 & 0x0F) << 4) | (self.universe as u16 & 0x0F)
+    }
+}
+
+impl From<u16> for PortAddress {
+    fn from(address: u16) -> Self {
+        Self::new((address >> 8) as u8, (address >> 4) as u8, address as u8)
+    }
+}
+
+pub struct ArtNetSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+    sequence: u8,
+    sync: bool,
+    packets_sent: u64,
+    last_error: Option<String>,
+}
+
+impl ArtNetSender {
+    /// Öffnet einen UDP-Socket auf einem freien Port und sendet an `target`
+    /// (Unicast an einen Node oder Broadcast, z.B. 2.255.255.255).
+    pub fn new(target: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_broadcast(true)?;
+
+        Ok(Self {
+            socket,
+            target,
+            sequence: 0,
+            sync: false,
+            packets_sent: 0,
+            last_error: None,
+        })
+    }
+
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: SocketAddr) {
+        self.target = target;
+    }
+
+    pub fn sequence(&self) -> u8 {
+        self.sequence
+    }
+
+    pub fn sync_enabled(&self) -> bool {
+        self.sync
+    }
+
+    /// Schaltet ArtSync nach jedem Batch ein oder aus, gesendet wird es bei
+    /// [`DmxOutput::flush`](crate::output::DmxOutput::flush).
+    pub fn set_sync(&mut self, sync: bool) {
+        self.sync = sync;
+    }
+
+    /// Sendet einen vollen DMX-Frame als ArtDmx-Paket an `port_address`.
+    pub fn send_dmx_to(&mut self, port_address: PortAddress, data: &[u8; DMX_CHANNELS]) -> io::Result<()> {
+        self.sequence = next_sequence(self.sequence);
+        let packet = build_art_dmx(self.sequence, 0, port_address, data);
+        self.send_packet(&packet)
+    }
+
+    pub fn send_sync(&mut self) -> io::Result<()> {
+        self.send_packet(&build_art_sync())
+    }
+
+    pub fn packets_sent(&self) -> u64 {
+        self.packets_sent
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    fn send_packet(&mut self, packet: &[u8]) -> io::Result<()> {
+        match self.socket.send_to(packet, self.target) {
+            Ok(_) => {
+                self.packets_sent += 1;
+                self.last_error = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.last_error = Some(err.to_string());
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Sequence 0 bedeutet "keine Sequenz", deshalb läuft der Zähler 1..=255.
+fn next_sequence(sequence: u8) -> u8 {
+    if sequence == u8::MAX { 1 } else { sequence + 1 }
+}
+
+fn write_header(packet: &mut Vec<u8>, op_code: u16) {
+    packet.extend_from_slice(ART_NET_ID);
+    packet.extend_from_slice(&op_code.to_le_bytes());
+    packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+}
+
+/// Baut ein ArtDmx-Paket. `data` wird auf eine gerade Länge (2..=512) aufgefüllt.
+pub fn build_art_dmx(sequence: u8, physical: u8, port_address: PortAddress, data: &[u8]) -> Vec<u8> {
+    let data = &data[..data.len().min(DMX_CHANNELS)];
+    let length = (data.len().max(2) + 1) & !1;
+
+    let mut packet = Vec::with_capacity(18 + length);
+    write_header(&mut packet, OP_DMX);
+    packet.push(sequence);
+    packet.push(physical);
+    packet.push((port_address.sub_net << 4) | port_address.universe);
+    packet.push(port_address.net);
+    packet.extend_from_slice(&(length as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet.resize(18 + length, 0);
+    packet
+}
+
+/// Baut ein ArtSync-Paket, mit dem Nodes die zuletzt empfangenen ArtDmx-Frames gemeinsam ausgeben.
+pub fn build_art_sync() -> Vec<u8> {
+    let mut packet = Vec::with_capacity(14);
+    write_header(&mut packet, OP_SYNC);
+    packet.push(0); // Aux1
+    packet.push(0); // Aux2
+    packet
+}
+
+/// Ein Art-Net Node, wie er sich in seinem ArtPollReply meldet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtNode {
+    pub ip: Ipv4Addr,
+    pub port: u16,
+    pub version: u16,
+    pub net_switch: u8,
+    pub sub_switch: u8,
+    pub oem: u16,
+    pub status1: u8,
+    pub esta_manufacturer: u16,
+    pub short_name: String,
+    pub long_name: String,
+    pub node_report: String,
+    pub num_ports: u16,
+    pub port_types: [u8; 4],
+    pub good_output: [u8; 4],
+    pub sw_in: [u8; 4],
+    pub sw_out: [u8; 4],
+    pub style: u8,
+    pub mac: [u8; 6],
+    pub bind_index: u8,
+    pub status2: u8,
+}
+
+impl ArtNode {
+    /// Beschreibung eines Controllers ohne Ports, mit der rustLamp auf ArtPoll antwortet.
+    pub fn controller(short_name: &str, long_name: &str) -> Self {
+        Self {
+            ip: Ipv4Addr::UNSPECIFIED,
+            port: ART_NET_PORT,
+            version: 0,
+            net_switch: 0,
+            sub_switch: 0,
+            oem: 0xFFFF,
+            status1: STATUS1_NORMAL,
+            esta_manufacturer: 0,
+            short_name: short_name.to_string(),
+            long_name: long_name.to_string(),
+            node_report: String::new(),
+            num_ports: 0,
+            port_types: [0; 4],
+            good_output: [0; 4],
+            sw_in: [0; 4],
+            sw_out: [0; 4],
+            style: STYLE_CONTROLLER,
+            mac: [0; 6],
+            bind_index: 0,
+            status2: STATUS2_PORT_ADDRESS_15_BIT,
+        }
+    }
+
+    /// Trägt bis zu vier ausgegebene Universen ein. Ein ArtPollReply kennt nur ein Net/SubNet,
+    /// deshalb bestimmt das erste Universum Net und SubNet für alle.
+    pub fn set_output_universes(&mut self, universes: &[PortAddress]) {
+        let universes = &universes[..universes.len().min(4)];
+        let first = universes.first().copied().unwrap_or_default();
+
+        self.net_switch = first.net;
+        self.sub_switch = first.sub_net;
+        self.num_ports = universes.len() as u16;
+        self.port_types = [0; 4];
+        self.good_output = [0; 4];
+        self.sw_out = [0; 4];
+        for (i, universe) in universes.iter().enumerate() {
+            self.port_types[i] = PORT_TYPE_OUTPUT;
+            self.good_output[i] = GOOD_OUTPUT_TRANSMITTING;
+            self.sw_out[i] = universe.universe;
+        }
+    }
+
+    /// Port-Adressen der Ausgänge, die der Node meldet.
+    pub fn output_universes(&self) -> Vec<PortAddress> {
+        let ports = (self.num_ports as usize).min(4);
+        self.sw_out[..ports]
+            .iter()
+            .map(|sw| PortAddress::new(self.net_switch, self.sub_switch, *sw))
+            .collect()
+    }
+}
+
+/// Baut ein ArtPoll-Paket, auf das alle Nodes mit einem ArtPollReply antworten.
+pub fn build_art_poll() -> Vec<u8> {
+    let mut packet = Vec::with_capacity(14);
+    write_header(&mut packet, OP_POLL);
+    packet.push(0); // Flags
+    packet.push(0); // DiagPriority
+    packet
+}
+
+/// Liest ein ArtPollReply. Gibt `None` zurück, wenn das Paket kein (vollständiges) ArtPollReply ist.
+pub fn parse_art_poll_reply(packet: &[u8]) -> Option<ArtNode> {
+    if packet.len() < POLL_REPLY_MIN_LENGTH || &packet[..8] != ART_NET_ID {
+        return None;
+    }
+    if u16::from_le_bytes([packet[8], packet[9]]) != OP_POLL_REPLY {
+        return None;
+    }
+
+    let optional = |index: usize| packet.get(index).copied().unwrap_or(0);
+
+    Some(ArtNode {
+        ip: Ipv4Addr::new(packet[10], packet[11], packet[12], packet[13]),
+        port: u16::from_le_bytes([packet[14], packet[15]]),
+        version: u16::from_be_bytes([packet[16], packet[17]]),
+        net_switch: packet[18] & 0x7F,
+        sub_switch: packet[19] & 0x0F,
+        oem: u16::from_be_bytes([packet[20], packet[21]]),
+        status1: packet[23],
+        esta_manufacturer: u16::from_le_bytes([packet[24], packet[25]]),
+        short_name: read_c_string(&packet[26..44]),
+        long_name: read_c_string(&packet[44..108]),
+        node_report: read_c_string(&packet[108..172]),
+        num_ports: u16::from_be_bytes([packet[172], packet[173]]),
+        port_types: packet[174..178].try_into().unwrap(),
+        good_output: packet[182..186].try_into().unwrap(),
+        sw_in: packet[186..190].try_into().unwrap(),
+        sw_out: packet[190..194].try_into().unwrap(),
+        style: packet[200],
+        mac: packet[201..207].try_into().unwrap(),
+        bind_index: optional(211),
+        status2: optional(212),
+    })
+}
+
+/// Baut das ArtPollReply, mit dem `node` sich im Netz meldet.
+pub fn build_art_poll_reply(node: &ArtNode) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(POLL_REPLY_LENGTH);
+    packet.extend_from_slice(ART_NET_ID);
+    packet.extend_from_slice(&OP_POLL_REPLY.to_le_bytes());
+    packet.extend_from_slice(&node.ip.octets());
+    packet.extend_from_slice(&node.port.to_le_bytes());
+    packet.extend_from_slice(&node.version.to_be_bytes());
+    packet.push(node.net_switch);
+    packet.push(node.sub_switch);
+    packet.extend_from_slice(&node.oem.to_be_bytes());
+    packet.push(0); // Ubea Version
+    packet.push(node.status1);
+    packet.extend_from_slice(&node.esta_manufacturer.to_le_bytes());
+    write_c_string(&mut packet, &node.short_name, 18);
+    write_c_string(&mut packet, &node.long_name, 64);
+    write_c_string(&mut packet, &node.node_report, 64);
+    packet.extend_from_slice(&node.num_ports.to_be_bytes());
+    packet.extend_from_slice(&node.port_types);
+    packet.extend_from_slice(&[0; 4]); // GoodInput
+    packet.extend_from_slice(&node.good_output);
+    packet.extend_from_slice(&node.sw_in);
+    packet.extend_from_slice(&node.sw_out);
+    packet.extend_from_slice(&[0; 6]); // AcnPriority, SwMacro, SwRemote, Spare
+    packet.push(node.style);
+    packet.extend_from_slice(&node.mac);
+    packet.extend_from_slice(&node.ip.octets()); // BindIp
+    packet.push(node.bind_index);
+    packet.push(node.status2);
+    packet.resize(POLL_REPLY_LENGTH, 0);
+    packet
+}
+
+/// Schreibt `text` nullterminiert in ein Feld fester Länge.
+fn write_c_string(packet: &mut Vec<u8>, text: &str, length: usize) {
+    let bytes = &text.as_bytes()[..text.len().min(length - 1)];
+    packet.extend_from_slice(bytes);
+    packet.resize(packet.len() + length - bytes.len(), 0);
+}
+
+fn read_c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Schickt ein ArtPoll an `broadcast` und sammelt alle Antworten, die innerhalb von `timeout` eintreffen.
+///
+/// Nodes antworten an Port 6454. Ist der Port schon belegt, wird auf einem freien Port gelauscht;
+/// dann melden sich nur Nodes, die an den Absender-Port zurückschicken.
+pub fn discover_nodes(broadcast: SocketAddr, timeout: Duration) -> io::Result<Vec<ArtNode>> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, ART_NET_PORT))
+        .or_else(|_| UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)))?;
+    socket.set_broadcast(true)?;
+    discover_nodes_on(&socket, broadcast, timeout)
+}
+
+/// Wie [`discover_nodes`], aber auf einem schon geöffneten Socket.
+pub fn discover_nodes_on(socket: &UdpSocket, broadcast: SocketAddr, timeout: Duration) -> io::Result<Vec<ArtNode>> {
+    socket.send_to(&build_art_poll(), broadcast)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut nodes: Vec<ArtNode> = Vec::new();
+    let mut buffer = [0u8; 1024];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining))?;
+
+        match socket.recv_from(&mut buffer) {
+            Ok((length, _)) => {
+                let Some(node) = parse_art_poll_reply(&buffer[..length]) else { continue };
+                // Nodes mit mehreren Bind-Indizes antworten mehrfach, die gleiche Antwort aber nur einmal aufnehmen
+                if !nodes.iter().any(|n| n.ip == node.ip && n.bind_index == node.bind_index) {
+                    nodes.push(node);
+                }
+            }
+            Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Lauscht auf Port 6454 und antwortet auf ArtPoll, damit rustLamp in Node-Listen und
+/// Netzwerk-Monitoren als Controller auftaucht. ArtPollReplies anderer Nodes werden dabei
+/// gesammelt, weil der Port für [`discover_nodes`] sonst belegt ist.
+pub struct ArtPollResponder {
+    socket: UdpSocket,
+    info: Arc<Mutex<ArtNode>>,
+    nodes: Arc<Mutex<Vec<ArtNode>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl ArtPollResponder {
+    pub fn start(info: ArtNode) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, ART_NET_PORT))?;
+        Self::start_on(socket, info)
+    }
+
+    /// Startet den Responder auf einem schon gebundenen Socket.
+    pub fn start_on(socket: UdpSocket, info: ArtNode) -> io::Result<Self> {
+        socket.set_broadcast(true)?;
+        // Mit Timeout, damit der Thread das Beenden mitbekommt
+        socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+
+        let responder = Self {
+            socket: socket.try_clone()?,
+            info: Arc::new(Mutex::new(info)),
+            nodes: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(true)),
+        };
+
+        let info = responder.info.clone();
+        let nodes = responder.nodes.clone();
+        let running = responder.running.clone();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 1024];
+            while running.load(Ordering::Relaxed) {
+                let (length, source) = match socket.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+                    Err(err) => {
+                        eprintln!("ArtPoll Responder beendet: {}", err);
+                        break;
+                    }
+                };
+                let packet = &buffer[..length];
+
+                if is_art_poll(packet) {
+                    let mut reply = info.lock().unwrap().clone();
+                    if let Some(ip) = local_ip_towards(source) {
+                        reply.ip = ip;
+                    }
+                    let target = SocketAddr::new(source.ip(), ART_NET_PORT);
+                    if let Err(err) = socket.send_to(&build_art_poll_reply(&reply), target) {
+                        eprintln!("ArtPollReply an {} fehlgeschlagen: {}", target, err);
+                    }
+                } else if let Some(node) = parse_art_poll_reply(packet) {
+                    // Die eigene Antwort auf das eigene ArtPoll nicht als Node aufnehmen
+                    if local_ip_towards(source) == Some(node.ip) {
+                        continue;
+                    }
+                    let mut nodes = nodes.lock().unwrap();
+                    if !nodes.iter().any(|n| n.ip == node.ip && n.bind_index == node.bind_index) {
+                        nodes.push(node);
+                    }
+                }
+            }
+        });
+
+        Ok(responder)
+    }
+
+    pub fn set_output_universes(&self, universes: &[PortAddress]) {
+        self.info.lock().unwrap().set_output_universes(universes);
+    }
+
+    /// Verwirft die bisher gefundenen Nodes und schickt ein neues ArtPoll.
+    /// Die Antworten stehen danach nach und nach in [`Self::nodes`].
+    pub fn poll(&self, broadcast: SocketAddr) -> io::Result<()> {
+        self.nodes.lock().unwrap().clear();
+        self.socket.send_to(&build_art_poll(), broadcast)?;
+        Ok(())
+    }
+
+    pub fn nodes(&self) -> Vec<ArtNode> {
+        self.nodes.lock().unwrap().clone()
+    }
+}
+
+impl Drop for ArtPollResponder {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn is_art_poll(packet: &[u8]) -> bool {
+    packet.len() >= 12 && &packet[..8] == ART_NET_ID && u16::from_le_bytes([packet[8], packet[9]]) == OP_POLL
+}
+
+/// Die eigene IPv4-Adresse auf dem Interface, über das `peer` erreicht wird.
+fn local_ip_towards(peer: SocketAddr) -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect(peer).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) => Some(ip),
+        IpAddr::V6(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::DmxOutput;
+
+    fn localhost() -> UdpSocket {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        socket
+    }
+
+    fn sample_node() -> ArtNode {
+        let mut node = ArtNode::controller("Testnode", "Node für die Tests");
+        node.ip = Ipv4Addr::new(127, 0, 0, 1);
+        node.version = 0x0102;
+        node.oem = 0x1234;
+        node.esta_manufacturer = 0x7A70;
+        node.node_report = "#0001 [0042] OK".to_string();
+        node.mac = [1, 2, 3, 4, 5, 6];
+        node.bind_index = 2;
+        node.set_output_universes(&[PortAddress::new(1, 2, 3), PortAddress::new(1, 2, 4)]);
+        node
+    }
+
+    #[test]
+    fn poll_reply_round_trip() {
+        let node = sample_node();
+        let packet = build_art_poll_reply(&node);
+        assert_eq!(packet.len(), POLL_REPLY_LENGTH);
+        assert_eq!(parse_art_poll_reply(&packet), Some(node.clone()));
+        assert_eq!(node.output_universes(), vec![PortAddress::new(1, 2, 3), PortAddress::new(1, 2, 4)]);
+    }
+
+    #[test]
+    fn old_poll_replies_are_accepted() {
+        let node = sample_node();
+        let packet = build_art_poll_reply(&node);
+
+        let old = parse_art_poll_reply(&packet[..POLL_REPLY_MIN_LENGTH]).unwrap();
+        assert_eq!(old.short_name, node.short_name);
+        assert_eq!((old.bind_index, old.status2), (0, 0));
+
+        assert_eq!(parse_art_poll_reply(&packet[..POLL_REPLY_MIN_LENGTH - 1]), None);
+        let mut wrong_op = packet.clone();
+        wrong_op[8..10].copy_from_slice(&OP_POLL.to_le_bytes());
+        assert_eq!(parse_art_poll_reply(&wrong_op), None);
+    }
+
+    #[test]
+    fn long_names_are_cut() {
+        let mut node = sample_node();
+        node.short_name = "x".repeat(30);
+        let parsed = parse_art_poll_reply(&build_art_poll_reply(&node)).unwrap();
+        assert_eq!(parsed.short_name, "x".repeat(17));
+    }
+
+    #[test]
+    fn discover_fake_node() {
+        let fake_node = localhost();
+        let node_address = fake_node.local_addr().unwrap();
+        let node = sample_node();
+
+        let answer = {
+            let node = node.clone();
+            thread::spawn(move || {
+                let mut buffer = [0u8; 1024];
+                let (length, source) = fake_node.recv_from(&mut buffer).unwrap();
+                assert!(is_art_poll(&buffer[..length]));
+                let reply = build_art_poll_reply(&node);
+                fake_node.send_to(b"kein Art-Net", source).unwrap();
+                fake_node.send_to(&reply, source).unwrap();
+                // Doppelte Antworten werden nur einmal aufgenommen
+                fake_node.send_to(&reply, source).unwrap();
+            })
+        };
+
+        let controller = localhost();
+        let nodes = discover_nodes_on(&controller, node_address, Duration::from_millis(300)).unwrap();
+        answer.join().unwrap();
+        assert_eq!(nodes, vec![node]);
+    }
+
+    #[test]
+    fn art_dmx_layout() {
+        let packet = build_art_dmx(7, 3, PortAddress::new(0x12, 0x4, 0x5), &[1, 2, 3]);
+
+        assert_eq!(&packet[..8], ART_NET_ID);
+        // OpCode little endian, Version big endian
+        assert_eq!(&packet[8..10], &[0x00, 0x50]);
+        assert_eq!(&packet[10..12], &[0, 14]);
+        assert_eq!(packet[12], 7);
+        assert_eq!(packet[13], 3);
+        assert_eq!(packet[14], 0x45);
+        assert_eq!(packet[15], 0x12);
+        // Ungerade Länge wird auf gerade aufgefüllt
+        assert_eq!(&packet[16..18], &[0, 4]);
+        assert_eq!(&packet[18..], &[1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn art_dmx_lengths() {
+        assert_eq!(build_art_dmx(1, 0, PortAddress::default(), &[]).len(), 18 + 2);
+        assert_eq!(build_art_dmx(1, 0, PortAddress::default(), &[9]).len(), 18 + 2);
+        assert_eq!(build_art_dmx(1, 0, PortAddress::default(), &[0; DMX_CHANNELS]).len(), 18 + DMX_CHANNELS);
+        assert_eq!(build_art_dmx(1, 0, PortAddress::default(), &[0; 600]).len(), 18 + DMX_CHANNELS);
+    }
+
+    #[test]
+    fn port_address_bits() {
+        let address = PortAddress::new(0xFF, 0x1F, 0x1F);
+        assert_eq!(address, PortAddress::new(0x7F, 0xF, 0xF));
+        assert_eq!(address.to_u16(), 0x7FFF);
+        assert_eq!(PortAddress::from(0x1234), PortAddress::new(0x12, 0x3, 0x4));
+    }
+
+    #[test]
+    fn sequence_skips_zero() {
+        assert_eq!(next_sequence(0), 1);
+        assert_eq!(next_sequence(254), 255);
+        assert_eq!(next_sequence(255), 1);
+    }
+
+    #[test]
+    fn sender_against_local_socket() {
+        let receiver = localhost();
+        let mut sender = ArtNetSender::new(receiver.local_addr().unwrap()).unwrap();
+        sender.set_sync(true);
+
+        let mut data = [0u8; DMX_CHANNELS];
+        data[0] = 255;
+        sender.send_universe(0x0123, &data).unwrap();
+        sender.send_universe(0x0123, &data).unwrap();
+        sender.flush().unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let mut receive = || {
+            let (length, _) = receiver.recv_from(&mut buffer).unwrap();
+            buffer[..length].to_vec()
+        };
+        let first = receive();
+        let second = receive();
+        let sync = receive();
+
+        assert_eq!(first, build_art_dmx(1, 0, PortAddress::from(0x0123), &data));
+        assert_eq!(second[12], 2);
+        assert_eq!(sync, build_art_sync());
+        assert_eq!(sender.sequence(), 2);
+        assert_eq!(sender.packets_sent(), 3);
+    }
+}
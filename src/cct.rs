@@ -0,0 +1,88 @@
+//! Farbtemperatur über den Planckschen Kurvenzug im CIE-1960-uv-Diagramm,
+//! genähert nach Krystek (1985), genau genug von 1000 K bis 15000 K.
+
+pub const KELVIN_MIN: u16 = 1000;
+pub const KELVIN_MAX: u16 = 15000;
+
+/// Farbort eines schwarzen Strahlers.
+pub fn planckian_uv(kelvin: f64) -> (f64, f64) {
+    let t = kelvin.clamp(KELVIN_MIN as f64, KELVIN_MAX as f64);
+    let t2 = t * t;
+    let u = (0.860_117_757 + 1.541_182_54e-4 * t + 1.286_412_12e-7 * t2)
+        / (1.0 + 8.424_202_35e-4 * t + 7.081_451_63e-7 * t2);
+    let v = (0.317_398_726 + 4.228_062_45e-5 * t + 4.204_816_91e-8 * t2)
+        / (1.0 - 2.897_418_16e-5 * t + 1.614_560_53e-7 * t2);
+    (u, v)
+}
+
+/// Einheitsvektor senkrecht zum Kurvenzug, zeigt zu positivem Duv (grünlich).
+fn planckian_normal(kelvin: f64) -> (f64, f64) {
+    let t = kelvin.clamp(KELVIN_MIN as f64 + 1.0, KELVIN_MAX as f64 - 1.0);
+    let (u1, v1) = planckian_uv(t - 1.0);
+    let (u2, v2) = planckian_uv(t + 1.0);
+    let (du, dv) = (u2 - u1, v2 - v1);
+    let length = (du * du + dv * dv).sqrt();
+    // u fällt mit steigender Temperatur, also zeigt (dv, -du) nach oben
+    (dv / length, -du / length)
+}
+
+/// Farbort zu Farbtemperatur und Abstand `duv` vom Kurvenzug (positiv grünlich, negativ magenta).
+pub fn cct_to_uv(kelvin: f64, duv: f64) -> (f64, f64) {
+    let (u, v) = planckian_uv(kelvin);
+    let (nu, nv) = planckian_normal(kelvin);
+    (u + duv * nu, v + duv * nv)
+}
+
+/// Nächstgelegene Farbtemperatur und Duv zu einem Farbort.
+///
+/// Gesucht wird in Mired, dort liegen die Temperaturen gleichmäßiger auf dem Kurvenzug.
+pub fn uv_to_cct((u, v): (f64, f64)) -> (f64, f64) {
+    let distance = |mired: f64| {
+        let (pu, pv) = planckian_uv(1e6 / mired);
+        (u - pu).powi(2) + (v - pv).powi(2)
+    };
+
+    let min_mired = 1e6 / KELVIN_MAX as f64;
+    let max_mired = 1e6 / KELVIN_MIN as f64;
+
+    // Grob in 1-Mired-Schritten, danach das Minimum eingrenzen
+    let mut best = min_mired;
+    let mut mired = min_mired;
+    while mired <= max_mired {
+        if distance(mired) < distance(best) {
+            best = mired;
+        }
+        mired += 1.0;
+    }
+    let mut low = (best - 1.0).max(min_mired);
+    let mut high = (best + 1.0).min(max_mired);
+    for _ in 0..40 {
+        let a = low + (high - low) / 3.0;
+        let b = high - (high - low) / 3.0;
+        if distance(a) < distance(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+
+    let kelvin = 1e6 / ((low + high) / 2.0);
+    let (pu, pv) = planckian_uv(kelvin);
+    let (nu, nv) = planckian_normal(kelvin);
+    let offset = (u - pu, v - pv);
+    let duv = (offset.0 * offset.0 + offset.1 * offset.1).sqrt();
+    let above = offset.0 * nu + offset.1 * nv >= 0.0;
+    (kelvin, if above { duv } else { -duv })
+}
+
+/// CIE 1931 xy nach CIE 1960 uv.
+pub fn xy_to_uv((x, y): (f64, f64)) -> (f64, f64) {
+    let d = -2.0 * x + 12.0 * y + 3.0;
+    (4.0 * x / d, 6.0 * y / d)
+}
+
+/// CIE 1960 uv nach CIE 1931 xy.
+pub fn uv_to_xy((u, v): (f64, f64)) -> (f64, f64) {
+    let d = 2.0 * u - 8.0 * v + 4.0;
+    (3.0 * u / d, 2.0 * v / d)
+}
@@ -1,81 +1,99 @@
+use gtk4::glib;
 use std::cmp::{max, min};
+use crate::{cct, color_space};
 
-#[derive(Debug, Clone, Copy)]
+/// Eine Farbe, gespeichert als sRGB mit 16 bit pro Kanal. CMY, HSV und alle anderen Modelle
+/// werden daraus berechnet und können so nicht auseinanderlaufen.
+///
+/// Bei Grau und Schwarz lassen sich Farbton und Sättigung nicht aus RGB ablesen. Dafür merkt sich
+/// `Color` den letzten Farbton, bei Schwarz auch die letzte Sättigung. So geht die Farbe nicht verloren,
+/// wenn die Lampe auf Schwarz und wieder hoch gedimmt wird, egal ob über RGB oder HSV.
+#[derive(Debug, Clone, Copy, PartialEq, glib::Boxed)]
+#[boxed_type(name = "RustLampColor")]
 pub struct Color {
-    pub red: u16,
-    pub green: u16,
-    pub blue: u16,
+    red: u16,
+    green: u16,
+    blue: u16,
 
-    pub cyan: u16,
-    pub magenta: u16,
-    pub yellow: u16,
+    /// Bei bunten Farben passen sie zu RGB, bei unbunten sind sie die zuletzt gewählten.
+    hue_hint: u16,
+    saturation_hint: u16,
+}
 
-    pub hue: u16,
-    pub saturation: u16,
-    pub value: u16,
+impl Default for Color {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Color {
+    /// Schwarz.
     pub fn new() -> Self {
         Self {
             red: 0,
             green: 0,
             blue: 0,
-            cyan: u16::MAX,
-            magenta: u16::MAX,
-            yellow: u16::MAX,
-            hue: 0,
-            saturation: 0,
-            value: 0,
+            hue_hint: 0,
+            saturation_hint: 0,
         }
     }
 
-    pub fn set_rgb(&mut self, red: u16, green: u16, blue: u16) {
-        self.red = red;
-        self.green = green;
-        self.blue = blue;
+    pub fn red(&self) -> u16 {
+        self.red
+    }
 
-        /* --- CMY --- */
-        self.cyan = u16::MAX - red;
-        self.magenta = u16::MAX - green;
-        self.yellow = u16::MAX - blue;
+    pub fn green(&self) -> u16 {
+        self.green
+    }
 
-        /* --- HSV --- */
-        let max_v = max(red, max(green, blue));
-        let min_v = min(red, min(green, blue));
-        let delta = max_v - min_v;
+    pub fn blue(&self) -> u16 {
+        self.blue
+    }
 
-        self.value = max_v;
+    pub fn cyan(&self) -> u16 {
+        u16::MAX - self.red
+    }
 
-        self.saturation = if max_v == 0 {
-            0
-        } else {
-            ((delta as u32 * u16::MAX as u32) / max_v as u32) as u16
-        };
+    pub fn magenta(&self) -> u16 {
+        u16::MAX - self.green
+    }
 
-        let mut h: f32 = if delta == 0 {
-            0.0
-        } else if max_v == red {
-            (green as f32 - blue as f32) / delta as f32
-        } else if max_v == green {
-            (blue as f32 - red as f32) / delta as f32 + 2.0
-        } else {
-            (red as f32 - green as f32) / delta as f32 + 4.0
-        };
+    pub fn yellow(&self) -> u16 {
+        u16::MAX - self.blue
+    }
 
-        if h < 0.0 {
-            h += 6.0;
+    pub fn hue(&self) -> u16 {
+        self.hue_hint
+    }
+
+    pub fn saturation(&self) -> u16 {
+        self.saturation_hint
+    }
+
+    pub fn value(&self) -> u16 {
+        max(self.red, max(self.green, self.blue))
+    }
+
+    pub fn set_rgb(&mut self, red: u16, green: u16, blue: u16) {
+        self.red = red;
+        self.green = green;
+        self.blue = blue;
+
+        let (hue, saturation) = hue_saturation(red, green, blue);
+        if let Some(hue) = hue {
+            self.hue_hint = hue;
+        }
+        if let Some(saturation) = saturation {
+            self.saturation_hint = saturation;
         }
+    }
 
-        self.hue = (h * (u16::MAX as f32 / 6.0)) as u16;
+    /// Subtraktive Mischung, z.B. für CMY-Farbfilter.
+    pub fn set_cmy(&mut self, cyan: u16, magenta: u16, yellow: u16) {
+        self.set_rgb(u16::MAX - cyan, u16::MAX - magenta, u16::MAX - yellow);
     }
 
     pub fn set_hsv(&mut self, hue: u16, saturation: u16, value: u16) {
-        self.hue = hue;
-        self.saturation = saturation;
-        self.value = value;
-        println!("HSV: {}, {}, {}", hue, saturation, value);
-
         let h = hue as f32 * 6.0 / u16::MAX as f32;
         let s = saturation as f32 / u16::MAX as f32;
         let v = value as f32 / u16::MAX as f32;
@@ -93,26 +111,125 @@ impl Color {
             _                 => (c, 0.0, x),
         };
 
-        self.red   = ((r1 + m) * u16::MAX as f32) as u16;
-        self.green = ((g1 + m) * u16::MAX as f32) as u16;
-        self.blue  = ((b1 + m) * u16::MAX as f32) as u16;
+        self.red = unit_to_u16(r1 + m);
+        self.green = unit_to_u16(g1 + m);
+        self.blue = unit_to_u16(b1 + m);
+        self.hue_hint = hue;
+        self.saturation_hint = saturation;
+    }
+
+    /// Weiß mit der Farbtemperatur `kelvin` bei voller Helligkeit, z.B. 2700 für Warmweiß.
+    pub fn set_kelvin(&mut self, kelvin: u16) {
+        self.set_cct_with_value(kelvin, 0.0, u16::MAX);
+    }
+
+    /// Farbtemperatur `kelvin`, um `duv` neben dem Planckschen Kurvenzug: positiv ins Grünliche,
+    /// negativ ins Magenta. Übliche Werte liegen bei ±0.02.
+    ///
+    /// Die Helligkeit (V) bleibt erhalten, nur Schwarz wird zu voller Helligkeit, sonst bliebe es schwarz.
+    pub fn set_cct(&mut self, kelvin: u16, duv: f32) {
+        let value = match self.value() {
+            0 => u16::MAX,
+            value => value,
+        };
+        self.set_cct_with_value(kelvin, duv, value);
+    }
+
+    fn set_cct_with_value(&mut self, kelvin: u16, duv: f32, value: u16) {
+        let xy = cct::uv_to_xy(cct::cct_to_uv(kelvin as f64, duv as f64));
+        let linear = color_space::xyz_to_linear_srgb(color_space::xy_to_xyz(xy, 1.0)).map(|c| c.max(0.0));
+        // Nicht darstellbare Anteile fallen weg, der hellste Kanal kommt auf `value`.
+        // Linear skaliert, damit sich der Farbort nicht verschiebt.
+        let brightest = linear.iter().copied().fold(0.0, f64::max);
+        if brightest <= 0.0 {
+            return;
+        }
+        let scale = color_space::srgb_to_linear(u16_to_unit(value) as f64) / brightest;
+        self.set_linear_rgb(linear.map(|c| c * scale));
+    }
+
+    /// Nächstgelegene Farbtemperatur in Kelvin und Duv der aktuellen Farbe, `None` für Schwarz.
+    pub fn cct(&self) -> Option<(u16, f32)> {
+        let xy = color_space::xyz_to_xy(self.xyz())?;
+        let (kelvin, duv) = cct::uv_to_cct(cct::xy_to_uv(xy));
+        Some((kelvin.round() as u16, duv as f32))
+    }
+
+    /// Lineares sRGB 0..1, also ohne Transferkurve. Davon gehen alle CIE- und OK-Räume aus.
+    pub fn linear_rgb(&self) -> [f64; 3] {
+        [self.red, self.green, self.blue].map(|c| color_space::srgb_to_linear(u16_to_unit(c) as f64))
+    }
+
+    /// Nicht darstellbare Farben werden kanalweise auf 0..1 abgeschnitten.
+    pub fn set_linear_rgb(&mut self, linear: [f64; 3]) {
+        let [r, g, b] = linear.map(|c| unit_to_u16(color_space::linear_to_srgb(c.clamp(0.0, 1.0)) as f32));
+        self.set_rgb(r, g, b);
+    }
+
+    /// CIE XYZ, Weiß hat Y = 1.
+    pub fn xyz(&self) -> [f64; 3] {
+        color_space::linear_srgb_to_xyz(self.linear_rgb())
+    }
+
+    pub fn set_xyz(&mut self, xyz: [f64; 3]) {
+        self.set_linear_rgb(color_space::xyz_to_linear_srgb(xyz));
+    }
+
+    /// Farbort x, y und Helligkeit Y.
+    pub fn xyy(&self) -> [f64; 3] {
+        color_space::xyz_to_xyy(self.xyz())
+    }
+
+    pub fn set_xyy(&mut self, xyy: [f64; 3]) {
+        self.set_xyz(color_space::xyy_to_xyz(xyy));
+    }
+
+    /// CIE L*a*b* mit L 0..100, bezogen auf D65.
+    pub fn lab(&self) -> [f64; 3] {
+        color_space::xyz_to_lab(self.xyz())
+    }
+
+    pub fn set_lab(&mut self, lab: [f64; 3]) {
+        self.set_xyz(color_space::lab_to_xyz(lab));
+    }
+
+    /// L*, Chroma und Farbton in Grad.
+    pub fn lch(&self) -> [f64; 3] {
+        color_space::to_polar(self.lab())
+    }
+
+    pub fn set_lch(&mut self, lch: [f64; 3]) {
+        self.set_lab(color_space::from_polar(lch));
+    }
+
+    /// OKLab mit L 0..1. Wahrnehmungsmäßig gleichmäßiger als HSV, z.B. für Überblendungen.
+    pub fn oklab(&self) -> [f64; 3] {
+        color_space::linear_srgb_to_oklab(self.linear_rgb())
+    }
+
+    pub fn set_oklab(&mut self, oklab: [f64; 3]) {
+        self.set_linear_rgb(color_space::oklab_to_linear_srgb(oklab));
+    }
+
+    /// OKLab als L, Chroma und Farbton in Grad.
+    pub fn oklch(&self) -> [f64; 3] {
+        color_space::to_polar(self.oklab())
+    }
 
-        self.cyan = u16::MAX - self.red;
-        self.magenta = u16::MAX - self.green;
-        self.yellow = u16::MAX - self.blue;
-        //println!("RGB: {}, {}, {}", self.red, self.green, self.blue);
+    pub fn set_oklch(&mut self, oklch: [f64; 3]) {
+        self.set_oklab(color_space::from_polar(oklch));
     }
 
     pub fn set_hue(&mut self, hue: u16) {
-        self.set_hsv(hue, self.saturation, self.value);
+        self.set_hsv(hue, self.saturation(), self.value());
     }
 
     pub fn set_saturation(&mut self, saturation: u16) {
-        self.set_hsv(self.hue, saturation, self.value);
+        self.set_hsv(self.hue(), saturation, self.value());
     }
 
     pub fn set_value(&mut self, value: u16) {
-        self.set_hsv(self.hue, self.saturation, value);
+        self.set_hsv(self.hue(), self.saturation(), value);
     }
 
     pub fn map_rgb_to_unit(&self) -> (f32, f32, f32) {
@@ -125,11 +242,83 @@ impl Color {
 
     pub fn map_hsv_to_unit(&self) -> (f32, f32, f32) {
         (
-            u16_to_unit(self.hue),
-            u16_to_unit(self.saturation),
-            u16_to_unit(self.value),
+            u16_to_unit(self.hue()),
+            u16_to_unit(self.saturation()),
+            u16_to_unit(self.value()),
         )
     }
+
+    /// Hex-Code wie in CSS, z.B. `#FF8000`, auf 8 bit gerundet.
+    pub fn hex(&self) -> String {
+        let to_8_bit = |x: u16| (x as u32 + 128) / 257;
+        format!("#{:02X}{:02X}{:02X}", to_8_bit(self.red), to_8_bit(self.green), to_8_bit(self.blue))
+    }
+
+    /// Grober Farbname, z.B. für Screenreader: "Dunkles Orange", "Hellgrau".
+    pub fn name(&self) -> String {
+        let (hue, saturation, value) = self.map_hsv_to_unit();
+        if value < 0.08 {
+            return "Schwarz".to_string();
+        }
+        if saturation < 0.12 {
+            let gray = match value {
+                v if v > 0.9 => "Weiß",
+                v if v > 0.6 => "Hellgrau",
+                v if v > 0.3 => "Grau",
+                _ => "Dunkelgrau",
+            };
+            return gray.to_string();
+        }
+
+        let base = match hue * 360.0 {
+            h if h < 15.0 => "Rot",
+            h if h < 45.0 => "Orange",
+            h if h < 70.0 => "Gelb",
+            h if h < 160.0 => "Grün",
+            h if h < 200.0 => "Cyan",
+            h if h < 255.0 => "Blau",
+            h if h < 290.0 => "Violett",
+            h if h < 345.0 => "Magenta",
+            _ => "Rot",
+        };
+        let prefix = if value < 0.5 {
+            "Dunkles "
+        } else if saturation < 0.5 {
+            "Helles "
+        } else {
+            ""
+        };
+        format!("{}{}", prefix, base)
+    }
+}
+
+/// Farbton und Sättigung aus RGB. Unbunte Farben haben keinen Farbton, Schwarz auch keine Sättigung.
+fn hue_saturation(red: u16, green: u16, blue: u16) -> (Option<u16>, Option<u16>) {
+    let max_v = max(red, max(green, blue));
+    let min_v = min(red, min(green, blue));
+    let delta = max_v - min_v;
+
+    if max_v == 0 {
+        return (None, None);
+    }
+    let saturation = ((delta as u32 * u16::MAX as u32) / max_v as u32) as u16;
+    if delta == 0 {
+        return (None, Some(saturation));
+    }
+
+    let mut h: f32 = if max_v == red {
+        (green as f32 - blue as f32) / delta as f32
+    } else if max_v == green {
+        (blue as f32 - red as f32) / delta as f32 + 2.0
+    } else {
+        (red as f32 - green as f32) / delta as f32 + 4.0
+    };
+
+    if h < 0.0 {
+        h += 6.0;
+    }
+
+    (Some((h * (u16::MAX as f32 / 6.0)) as u16), Some(saturation))
 }
 
 #[inline]
@@ -137,4 +326,145 @@ fn u16_to_unit(x: u16) -> f32 {
     x as f32 / u16::MAX as f32
 }
 
+#[inline]
+fn unit_to_u16(x: f32) -> u16 {
+    (x.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: u16, expected: u16, tolerance: u16) {
+        assert!(actual.abs_diff(expected) <= tolerance, "{} statt {} (±{})", actual, expected, tolerance);
+    }
+
+    #[test]
+    fn black_via_rgb_keeps_hue_and_saturation() {
+        let mut color = Color::new();
+        color.set_hsv(20000, 40000, 50000);
+        let before = color;
+
+        color.set_rgb(0, 0, 0);
+        assert_eq!((color.hue(), color.saturation(), color.value()), (20000, 40000, 0));
 
+        color.set_value(50000);
+        assert_eq!(color, before);
+    }
+
+    #[test]
+    fn gray_via_rgb_keeps_hue() {
+        let mut color = Color::new();
+        color.set_hsv(20000, 40000, 50000);
+
+        color.set_rgb(30000, 30000, 30000);
+        assert_eq!(color.hue(), 20000);
+        assert_eq!(color.saturation(), 0);
+
+        color.set_saturation(40000);
+        assert_eq!(color.hue(), 20000);
+        assert!(color.red() != color.green() || color.green() != color.blue());
+    }
+
+    #[test]
+    fn black_via_value_and_back_via_rgb() {
+        let mut color = Color::new();
+        color.set_hsv(20000, 40000, 50000);
+        let (red, green, blue) = (color.red(), color.green(), color.blue());
+
+        color.set_value(0);
+        assert_eq!((color.red(), color.green(), color.blue()), (0, 0, 0));
+        assert_eq!((color.hue(), color.saturation()), (20000, 40000));
+
+        // Aus RGB neu berechnet, daher nur bis auf Rundung gleich
+        color.set_rgb(red, green, blue);
+        assert_near(color.hue(), 20000, 2);
+        assert_near(color.saturation(), 40000, 2);
+    }
+
+    fn rgb(color: &Color) -> [u16; 3] {
+        [color.red(), color.green(), color.blue()]
+    }
+
+    fn sample_colors() -> Vec<Color> {
+        [[65535, 65535, 65535], [65535, 0, 0], [1000, 52000, 9000], [50000, 30000, 12000], [300, 9000, 40000]]
+            .into_iter()
+            .map(|[r, g, b]| {
+                let mut color = Color::new();
+                color.set_rgb(r, g, b);
+                color
+            })
+            .collect()
+    }
+
+    fn assert_round_trip(name: &str, convert: impl Fn(&Color) -> Color) {
+        for color in sample_colors() {
+            let back = convert(&color);
+            for (actual, expected) in rgb(&back).into_iter().zip(rgb(&color)) {
+                assert!(actual.abs_diff(expected) <= 2, "{}: {:?} statt {:?}", name, rgb(&back), rgb(&color));
+            }
+        }
+    }
+
+    #[test]
+    fn color_space_round_trips() {
+        let via = |set: fn(&mut Color, [f64; 3]), get: fn(&Color) -> [f64; 3]| {
+            move |color: &Color| {
+                let mut back = Color::new();
+                set(&mut back, get(color));
+                back
+            }
+        };
+        assert_round_trip("XYZ", via(Color::set_xyz, Color::xyz));
+        assert_round_trip("xyY", via(Color::set_xyy, Color::xyy));
+        assert_round_trip("Lab", via(Color::set_lab, Color::lab));
+        assert_round_trip("LCh", via(Color::set_lch, Color::lch));
+        assert_round_trip("OKLab", via(Color::set_oklab, Color::oklab));
+        assert_round_trip("OKLCh", via(Color::set_oklch, Color::oklch));
+    }
+
+    #[test]
+    fn white_reference_values() {
+        let mut white = Color::new();
+        white.set_rgb(u16::MAX, u16::MAX, u16::MAX);
+
+        let [l, a, b] = white.lab();
+        assert!((l - 100.0).abs() < 0.01 && a.abs() < 0.01 && b.abs() < 0.01, "{:?}", [l, a, b]);
+        let [l, a, b] = white.oklab();
+        assert!((l - 1.0).abs() < 1e-4 && a.abs() < 1e-4 && b.abs() < 1e-4, "{:?}", [l, a, b]);
+    }
+
+    #[test]
+    fn cct_keeps_brightness() {
+        let mut color = Color::new();
+        color.set_hsv(20000, 40000, 30000);
+
+        color.set_cct(4000, 0.0);
+        assert_near(color.value(), 30000, 1);
+        let (kelvin, duv) = color.cct().unwrap();
+        assert_near(kelvin, 4000, 15);
+        assert!(duv.abs() < 0.001, "Duv {}", duv);
+
+        color.set_cct(4000, 0.01);
+        assert_near(color.value(), 30000, 1);
+    }
+
+    #[test]
+    fn cct_from_black_is_full_brightness() {
+        let mut color = Color::new();
+        color.set_cct(3000, 0.0);
+        assert_eq!(color.value(), u16::MAX);
+        let (kelvin, _) = color.cct().unwrap();
+        assert_near(kelvin, 3000, 15);
+    }
+
+    #[test]
+    fn kelvin_is_full_brightness() {
+        let mut color = Color::new();
+        color.set_kelvin(2700);
+        assert_eq!(color.value(), u16::MAX);
+        assert_eq!(color.hex(), "#FFAD58");
+    }
+}
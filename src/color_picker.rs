@@ -1,373 +1,855 @@
 // language: rust
 use gtk4::prelude::*;
-use gtk4::{self as gtk, cairo, Box as GtkBox, DrawingArea, Stack};
+use gtk4::subclass::prelude::*;
+use gtk4::{self as gtk, cairo, gdk, glib, Box as GtkBox, DrawingArea, Stack};
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use crate::cct;
 use crate::color::Color;
+use crate::wheel_raster::{self, PixelRect, Point};
 
-pub struct ColorPicker {
+type Listener = Rc<dyn Fn(&Color)>;
+
+/// Kleinste Kantenlänge des Farbrads. Mehr Platz nimmt es sich, wenn es ihn bekommt.
+const WHEEL_MIN_SIZE: i32 = 240;
+
+/// Welcher Teil des Rads gerade gezogen wird.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DragTarget {
+    None,
+    Ring,
+    Triangle,
+}
+
+/// Verteilt Farbänderungen an alle Ansichten (Rad, RGB-Seite, ...), damit sie synchron bleiben.
+#[derive(Default)]
+struct Views {
+    listeners: RefCell<Vec<Listener>>,
+    updating: Cell<bool>,
+}
+
+impl Views {
+    fn subscribe(&self, listener: impl Fn(&Color) + 'static) {
+        self.listeners.borrow_mut().push(Rc::new(listener));
+    }
+
+    /// Aktualisiert alle Ansichten. Währenddessen ist `is_updating()` gesetzt, damit die
+    /// Signal-Handler der Regler das Nachführen nicht wieder als Benutzereingabe werten.
+    ///
+    /// Ruft ein Listener selbst `notify` auf, z.B. über `set_color`, bleibt das Flag danach gesetzt,
+    /// bis auch der äußere Aufruf fertig ist.
+    fn notify(&self, color: &Color) {
+        let listeners = self.listeners.borrow().clone();
+        let was_updating = self.updating.replace(true);
+        for listener in listeners {
+            listener(color);
+        }
+        self.updating.set(was_updating);
+    }
+
+    fn is_updating(&self) -> bool {
+        self.updating.get()
+    }
+}
+
+/// Was sich beim Ziehen kaum ändert und deshalb nicht jeden Frame neu gemalt wird:
+/// der Ring hängt nur von der Größe ab, das Dreieck zusätzlich vom Farbton.
+#[derive(Default)]
+struct WheelCache {
+    ring: Option<((i32, i32), cairo::ImageSurface)>,
+    triangle: Option<((i32, i32, u16), PixelRect, cairo::ImageSurface)>,
+}
+
+/// Die Kinder des Pickers, die von den Property-Settern gebraucht werden.
+struct Widgets {
     root: GtkBox,
-    color: Rc<RefCell<Color>>,
+    stack: Stack,
+    rgb_page: GtkBox,
+    cmy_page: GtkBox,
+    sixteen_bit: gtk::CheckButton,
+}
+
+mod imp {
+    use super::*;
+    use std::cell::OnceCell;
+    use std::sync::OnceLock;
+    use gtk4::glib::subclass::Signal;
+
+    #[derive(glib::Properties)]
+    #[properties(wrapper_type = super::ColorPicker)]
+    pub struct ColorPicker {
+        #[property(get = Self::color, set = Self::set_color, type = Color, explicit_notify)]
+        pub(super) color: Rc<RefCell<Color>>,
+        #[property(get, set = Self::set_show_rgb, default = true)]
+        show_rgb: Cell<bool>,
+        #[property(get, set = Self::set_show_cmy, default = true)]
+        show_cmy: Cell<bool>,
+        /// 8 oder 16, gilt für alle Regler-Seiten.
+        #[property(get, set = Self::set_bit_depth, minimum = 8, maximum = 16, default = 8)]
+        bit_depth: Cell<u32>,
+        pub(super) views: Rc<Views>,
+        pub(super) widgets: OnceCell<Widgets>,
+    }
+
+    impl Default for ColorPicker {
+        fn default() -> Self {
+            Self {
+                color: Rc::new(RefCell::new(Color::new())),
+                show_rgb: Cell::new(true),
+                show_cmy: Cell::new(true),
+                bit_depth: Cell::new(8),
+                views: Rc::new(Views::default()),
+                widgets: OnceCell::new(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ColorPicker {
+        const NAME: &'static str = "RustLampColorPicker";
+        type Type = super::ColorPicker;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("colorpicker");
+            klass.set_accessible_role(gtk::AccessibleRole::Group);
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for ColorPicker {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().build();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("color-changed")
+                    .param_types([Color::static_type()])
+                    .build()]
+            })
+        }
+
+        fn dispose(&self) {
+            if let Some(widgets) = self.widgets.get() {
+                widgets.root.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for ColorPicker {}
+
+    impl ColorPicker {
+        fn color(&self) -> Color {
+            *self.color.borrow()
+        }
+
+        /// Aktualisiert alle Ansichten, `notify::color` kommt über deren Listener.
+        fn set_color(&self, color: Color) {
+            *self.color.borrow_mut() = color;
+            self.views.notify(&color);
+        }
+
+        fn set_show_rgb(&self, show: bool) {
+            self.show_rgb.set(show);
+            if let Some(widgets) = self.widgets.get() {
+                widgets.stack.page(&widgets.rgb_page).set_visible(show);
+            }
+        }
+
+        fn set_show_cmy(&self, show: bool) {
+            self.show_cmy.set(show);
+            if let Some(widgets) = self.widgets.get() {
+                widgets.stack.page(&widgets.cmy_page).set_visible(show);
+            }
+        }
+
+        fn set_bit_depth(&self, bit_depth: u32) {
+            let bit_depth = if bit_depth >= 16 { 16 } else { 8 };
+            self.bit_depth.set(bit_depth);
+            if let Some(widgets) = self.widgets.get() {
+                widgets.sixteen_bit.set_active(bit_depth == 16);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Farbwähler mit HSV-Rad und Regler-Seiten für RGB und CMY.
+    ///
+    /// Kann auch in .ui-Dateien als `RustLampColorPicker` benutzt werden,
+    /// vorher muss der Typ mit `ColorPicker::ensure_type()` registriert sein.
+    pub struct ColorPicker(ObjectSubclass<imp::ColorPicker>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for ColorPicker {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ColorPicker {
-    pub fn new(color: Rc<RefCell<Color>>) -> Self {
-        let color_rc = Rc::clone(&color);
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Signal `color-changed`: kommt nach jeder Farbänderung, egal ob sie aus dem Rad, einer
+    /// Regler-Seite oder über die `color`-Property kommt. Mit der Id lässt sich der Handler wieder trennen.
+    pub fn connect_color_changed<F: Fn(&Self, Color) + 'static>(&self, callback: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "color-changed",
+            false,
+            glib::closure_local!(move |picker: &ColorPicker, color: Color| callback(picker, color)),
+        )
+    }
 
-        let circle_radius = Rc::new(Cell::new(0.0_f64));
-        let mouse_angle = Rc::new(RefCell::new(0.0_f64));
-        let left_button_pressed = Rc::new(RefCell::new(false));
+    fn build(&self) {
+        let imp = self.imp();
+        let color_rc = imp.color.clone();
 
         let root = GtkBox::new(gtk::Orientation::Vertical, 8);
         let stack = Stack::new();
 
-        let rgb_box = GtkBox::new(gtk::Orientation::Horizontal, 4);
-        rgb_box.set_visible(true);
-        let cmy_box = GtkBox::new(gtk::Orientation::Horizontal, 4);
-        cmy_box.set_visible(true);
-        let hsv_box = GtkBox::new(gtk::Orientation::Horizontal, 4);
-
-        let drawing = DrawingArea::new();
-        drawing.set_content_width(500);
-        drawing.set_content_height(500);
+        let views = imp.views.clone();
 
-        let point_a = Rc::new(RefCell::new((0.0_f64, 0.0_f64)));
-        let point_b = Rc::new(RefCell::new((0.0_f64, 0.0_f64)));
-        let point_c = Rc::new(RefCell::new((0.0_f64, 0.0_f64)));
+        // `notify::color` und `color-changed` für jede Änderung, auch die aus dem Rad und den Reglern
+        {
+            let picker = self.downgrade();
+            views.subscribe(move |color| {
+                if let Some(picker) = picker.upgrade() {
+                    picker.notify_color();
+                    picker.emit_by_name::<()>("color-changed", &[color]);
+                }
+            });
+        }
 
-        // ⚡ Punkt-Klone für Draw- und Click-Handler getrennt
-        let point_a_for_draw = point_a.clone();
-        let point_b_for_draw = point_b.clone();
-        let point_c_for_draw = point_c.clone();
+        // Gilt für alle Regler-Seiten
+        let sixteen_bit = gtk::CheckButton::with_label("16 bit");
 
-        let point_a_for_click = point_a.clone();
-        let point_b_for_click = point_b.clone();
-        let point_c_for_click = point_c.clone();
+        let rgb_box = GtkBox::new(gtk::Orientation::Vertical, 4);
+        rgb_box.set_visible(true);
+        build_channel_page(
+            &rgb_box,
+            ["R", "G", "B"],
+            |c| [c.red(), c.green(), c.blue()],
+            |c, [r, g, b]| c.set_rgb(r, g, b),
+            &sixteen_bit,
+            &color_rc,
+            &views,
+        );
+
+        // Subtraktiv, z.B. für Moving Heads mit dichroitischen Filtern
+        let cmy_box = GtkBox::new(gtk::Orientation::Vertical, 4);
+        cmy_box.set_visible(true);
+        build_channel_page(
+            &cmy_box,
+            ["C", "M", "Y"],
+            |c| [c.cyan(), c.magenta(), c.yellow()],
+            |c, [cyan, magenta, yellow]| c.set_cmy(cyan, magenta, yellow),
+            &sixteen_bit,
+            &color_rc,
+            &views,
+        );
+        let hsv_box = GtkBox::new(gtk::Orientation::Horizontal, 4);
 
-        // Draw-Funktion
+        // Für Screenreader ist das Rad ein Schieberegler für den Farbton, dessen Wert Farbname und Hex-Code nennt.
+        // Sättigung und Helligkeit haben eigene Zahlenfelder daneben.
+        let drawing = DrawingArea::builder().accessible_role(gtk::AccessibleRole::Slider).build();
+        drawing.update_property(&[
+            gtk::accessible::Property::Label("Farbrad"),
+            gtk::accessible::Property::Description(
+                "Pfeil links und rechts: Farbton, Pfeil hoch und runter: Helligkeit, Bild hoch und runter: Sättigung",
+            ),
+            gtk::accessible::Property::ValueMin(0.0),
+            gtk::accessible::Property::ValueMax(360.0),
+        ]);
+        drawing.set_content_width(WHEEL_MIN_SIZE);
+        drawing.set_content_height(WHEEL_MIN_SIZE);
+        drawing.set_hexpand(true);
+        drawing.set_vexpand(true);
+
+        // Zeichnen liest die Farbe nur. Geändert wird sie ausschließlich von den Eingabe-Handlern.
         {
-            let mouse_angle = mouse_angle.clone();
             let color_for_draw = color_rc.clone();
-            let circle_radius = circle_radius.clone();
+            let cache = RefCell::new(WheelCache::default());
             drawing.set_draw_func(move |_, cr, width, height| {
-                let cx = width as f64 / 2.0;
-                let cy = height as f64 / 2.0;
-                let circle_width = 10.0;
-                let radius = cx.min(cy) - circle_width;
-                circle_radius.set(radius - circle_width / 1.50);
-
-                let offset = -std::f64::consts::PI / 2.0;
-                for i in 0..360 {
-                    let angle1 = (i as f64).to_radians() + offset - 0.005;
-                    let angle2 = ((i + 1) as f64).to_radians() + offset;
-                    let (r, g, b) = hsv_to_rgb(i as f64 / 360.0, 1.0, 1.0);
-                    cr.set_source_rgb(r, g, b);
-                    cr.set_line_width(circle_width * 2.0);
-                    cr.arc(cx, cy, radius, angle1, angle2);
-                    cr.stroke().unwrap();
-                }
-
-                let triangle_radius = radius - circle_width;
-                let triangle_angle = 2.0 * std::f64::consts::PI / 3.0;
-                let angle_offset = *mouse_angle.borrow();
-
-                let mut points = Vec::with_capacity(3);
-                for j in 0..3 {
-                    let angle = -std::f64::consts::PI / 2.0 + j as f64 * triangle_angle + angle_offset;
-                    let x = cx + triangle_radius * angle.cos();
-                    let y = cy + triangle_radius * angle.sin();
-                    points.push((x, y));
-                }
+                let color = *color_for_draw.borrow();
+                draw_wheel(cr, width, height, &color, &mut cache.borrow_mut());
+            });
+        }
+        // Ring und Dreieck drehen sich mit, wenn die Farbe von einer anderen Seite kommt
+        {
+            let drawing = drawing.clone();
+            let show = move |color: &Color| {
+                drawing.queue_draw();
+                let (hue, _, _) = color.map_hsv_to_unit();
+                drawing.update_property(&[
+                    gtk::accessible::Property::ValueNow((hue * 360.0).round() as f64),
+                    gtk::accessible::Property::ValueText(&format!("{}, {}", color.name(), color.hex())),
+                ]);
+            };
+            // Screenreader sollen den Wert schon vor der ersten Änderung finden
+            show(&color_rc.borrow());
+            views.subscribe(show);
+        }
 
-                // Punkte in Draw-RefCells schreiben
-                *point_a_for_draw.borrow_mut() = points[0];
-                *point_b_for_draw.borrow_mut() = points[1];
-                *point_c_for_draw.borrow_mut() = points[2];
-
-                let angle = -*mouse_angle.borrow();
-                let hue = ((-angle).rem_euclid(2.0 * std::f64::consts::PI)) / (2.0 * std::f64::consts::PI);
-
-                color_rc.borrow_mut().set_hue(
-                    map(hue as f32, 0.0, 1.0, 0.0, u16::MAX as f32) as u16,
-                );
-
-                let (h_r, h_g, h_b) = hsv_to_rgb(hue, 1.0, 1.0);
-
-                cr.move_to(points[0].0, points[0].1);
-                cr.line_to(points[1].0, points[1].1);
-                cr.line_to(points[2].0, points[2].1);
-                cr.close_path();
-
-                let w = width as i32;
-                let h = height as i32;
-                let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, w, h).unwrap();
-                let stride = surface.stride() as usize;
-                let mut data = surface.data().unwrap();
-
-                let c0 = (h_r, h_g, h_b);
-                let c1 = (1.0, 1.0, 1.0);
-                let c2 = (0.0, 0.0, 0.0);
-
-                let (x0, y0) = points[0];
-                let (x1, y1) = points[1];
-                let (x2, y2) = points[2];
-
-                let min_x = (points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min).floor() as i32).clamp(0, w - 1);
-                let max_x = (points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max).ceil() as i32).clamp(0, w - 1);
-                let min_y = (points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).floor() as i32).clamp(0, h - 1);
-                let max_y = (points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil() as i32).clamp(0, h - 1);
-
-                let denom = (y1 - y2) * (x0 - x2) + (x2 - x1) * (y0 - y2);
-
-                for py in min_y..=max_y {
-                    for px in min_x..=max_x {
-                        let fx = px as f64 + 0.5;
-                        let fy = py as f64 + 0.5;
-                        let w0 = ((y1 - y2) * (fx - x2) + (x2 - x1) * (fy - y2)) / denom;
-                        let w1 = ((y2 - y0) * (fx - x2) + (x0 - x2) * (fy - y2)) / denom;
-                        let w2 = 1.0 - w0 - w1;
-                        if w0 >= -1e-6 && w1 >= -1e-6 && w2 >= -1e-6 {
-                            let rr = w0 * c0.0 + w1 * c1.0 + w2 * c2.0;
-                            let gg = w0 * c0.1 + w1 * c1.1 + w2 * c2.1;
-                            let bb = w0 * c0.2 + w1 * c1.2 + w2 * c2.2;
-                            let r8 = (rr * 255.0).clamp(0.0, 255.0) as u32;
-                            let g8 = (gg * 255.0).clamp(0.0, 255.0) as u32;
-                            let b8 = (bb * 255.0).clamp(0.0, 255.0) as u32;
-                            let a8 = 255u32;
-                            let pixel = (a8 << 24) | (r8 << 16) | (g8 << 8) | b8;
-                            let bytes = pixel.to_ne_bytes();
-                            let idx = py as usize * stride + px as usize * 4;
-                            data[idx..idx + 4].copy_from_slice(&bytes);
-                        }
+        // Ein Drag bleibt bei dem Teil, auf dem er angefangen hat:
+        // Ring → Farbton, Dreieck → Sättigung und Helligkeit
+        let drag = gtk4::GestureDrag::new();
+        drag.set_button(1);
+        let drag_target = Rc::new(Cell::new(DragTarget::None));
+        let drag_start = Rc::new(Cell::new((0.0_f64, 0.0_f64)));
+
+        let apply_drag = {
+            let drag_target = drag_target.clone();
+            let drawing = drawing.clone();
+            let color_rc = color_rc.clone();
+            let views = views.clone();
+            move |x: f64, y: f64| {
+                let geometry = WheelGeometry::new(drawing.width(), drawing.height(), &color_rc.borrow());
+                match drag_target.get() {
+                    DragTarget::None => return,
+                    DragTarget::Ring => {
+                        let (cx, cy) = geometry.center;
+                        let angle = (x - cx).atan2(-(y - cy));
+                        let hue = angle.rem_euclid(2.0 * std::f64::consts::PI) / (2.0 * std::f64::consts::PI);
+                        color_rc.borrow_mut().set_hue(map(hue as f32, 0.0, 1.0, 0.0, u16::MAX as f32) as u16);
+                    }
+                    DragTarget::Triangle => {
+                        let mut c = color_rc.borrow_mut();
+                        let (_, current_saturation, _) = c.map_hsv_to_unit();
+                        let [hue_point, white, black] = geometry.triangle;
+                        let (saturation, value) =
+                            triangle_to_sv((x, y), hue_point, white, black, current_saturation as f64);
+                        let hue = c.hue();
+                        c.set_hsv(
+                            hue,
+                            map(saturation as f32, 0.0, 1.0, 0.0, u16::MAX as f32) as u16,
+                            map(value as f32, 0.0, 1.0, 0.0, u16::MAX as f32) as u16,
+                        );
                     }
                 }
+                let c = *color_rc.borrow();
+                views.notify(&c);
+            }
+        };
+        let apply_drag = Rc::new(apply_drag);
 
-                drop(data);
-                cr.set_source_surface(&surface, 0.0, 0.0).unwrap();
-                cr.paint().unwrap();
+        {
+            let drag_target = drag_target.clone();
+            let drag_start = drag_start.clone();
+            let drawing = drawing.clone();
+            let color_rc = color_rc.clone();
+            let apply_drag = apply_drag.clone();
+            drag.connect_drag_begin(move |_, x, y| {
+                drawing.grab_focus();
+                drag_start.set((x, y));
+                let geometry = WheelGeometry::new(drawing.width(), drawing.height(), &color_rc.borrow());
+                // Innerhalb des Rings zählt alles zum Dreieck, Punkte daneben werden auf den Rand gezogen
+                let target = if calculate_distance((x, y), geometry.center) >= geometry.ring_inner_radius {
+                    DragTarget::Ring
+                } else {
+                    DragTarget::Triangle
+                };
+                drag_target.set(target);
+                apply_drag(x, y);
             });
         }
-        let pa = point_a_for_click.clone();
-        let pb = point_b_for_click.clone();
-        let pc = point_c_for_click.clone();
+        {
+            let drag_start = drag_start.clone();
+            drag.connect_drag_update(move |_, dx, dy| {
+                // dx, dy sind relativ zum Startpunkt
+                let (sx, sy) = drag_start.get();
+                apply_drag(sx + dx, sy + dy);
+            });
+        }
+        drag.connect_drag_end(move |_, _, _| {
+            drag_target.set(DragTarget::None);
+        });
+        drawing.add_controller(drag);
 
-        // Mausbewegung etc. bleibt unverändert...
-        // Klick-Handler nutzt nun die _for_click Klone
-        let motion_controller = gtk4::EventControllerMotion::new();
+        // Tastatur und Mausrad, zum genauen Einstellen ohne Ziehen
+        drawing.set_focusable(true);
+        let apply_nudge = {
+            let color_rc = color_rc.clone();
+            let views = views.clone();
+            Rc::new(move |channel: HsvChannel, delta: i32| {
+                nudge(&mut color_rc.borrow_mut(), channel, delta);
+                let c = *color_rc.borrow();
+                views.notify(&c);
+            })
+        };
+        {
+            let key = gtk::EventControllerKey::new();
+            let sixteen_bit = sixteen_bit.clone();
+            let apply_nudge = apply_nudge.clone();
+            key.connect_key_pressed(move |_, keyval, _, state| {
+                let (channel, direction) = match keyval {
+                    gdk::Key::Left => (HsvChannel::Hue, -1),
+                    gdk::Key::Right => (HsvChannel::Hue, 1),
+                    gdk::Key::Up => (HsvChannel::Value, 1),
+                    gdk::Key::Down => (HsvChannel::Value, -1),
+                    gdk::Key::Page_Up => (HsvChannel::Saturation, 1),
+                    gdk::Key::Page_Down => (HsvChannel::Saturation, -1),
+                    _ => return glib::Propagation::Proceed,
+                };
+                apply_nudge(channel, direction * nudge_step(state, sixteen_bit.is_active()));
+                glib::Propagation::Stop
+            });
+            drawing.add_controller(key);
+        }
         {
-            let mouse_angle = mouse_angle.clone();
-            let left_button_pressed = left_button_pressed.clone();
-            let drawing_clone = drawing.clone();
-            motion_controller.connect_motion(move |_, x, y| {
-                if *left_button_pressed.borrow() {
-                    let cx = drawing_clone.width() as f64 / 2.0;
-                    let cy = drawing_clone.height() as f64 / 2.0;
-                    let dx = x - cx;
-                    let dy = y - cy;
-                    let angle = dx.atan2(-dy);
-                    *mouse_angle.borrow_mut() = angle;
-                    drawing_clone.queue_draw();
-
-                    let pa = pa.borrow();
-                    let pb = pb.borrow();
-                    let pc = pc.borrow();
-
-                    let distance_a = calculate_distance((x,y),(pa.0,pa.1));//((x - pa.0).powi(2) + (y - pa.1).powi(2)).sqrt();
-                    let distance_b = calculate_distance((x,y),(pb.0,pb.1));//((x - pb.0).powi(2) + (y - pb.1).powi(2)).sqrt();
-                    let distance_c = calculate_distance((x,y),(pc.0,pc.1));//((x - pc.0).powi(2) + (y - pc.1).powi(2)).sqrt();
-
-                    //println!("{}", distance_a);
-                    //println!("{}", distance_b);
-                    //println!("{}", distance_c);
+            // Das Mausrad verstellt, was unter dem Zeiger liegt: Ring → Farbton, Dreieck → Helligkeit
+            let pointer = Rc::new(Cell::new((0.0_f64, 0.0_f64)));
+            let motion = gtk::EventControllerMotion::new();
+            {
+                let pointer = pointer.clone();
+                motion.connect_motion(move |_, x, y| pointer.set((x, y)));
+            }
+            drawing.add_controller(motion);
+
+            let scroll = gtk::EventControllerScroll::new(
+                gtk::EventControllerScrollFlags::BOTH_AXES | gtk::EventControllerScrollFlags::DISCRETE,
+            );
+            let drawing_for_scroll = drawing.clone();
+            let color_rc = color_rc.clone();
+            let sixteen_bit = sixteen_bit.clone();
+            scroll.connect_scroll(move |controller, dx, dy| {
+                let step = nudge_step(controller.current_event_state(), sixteen_bit.is_active());
+                let geometry = WheelGeometry::new(drawing_for_scroll.width(), drawing_for_scroll.height(), &color_rc.borrow());
+                let on_ring = calculate_distance(pointer.get(), geometry.center) >= geometry.ring_inner_radius;
+                // Nach oben bzw. rechts scrollen erhöht
+                if dy != 0.0 {
+                    let channel = if on_ring { HsvChannel::Hue } else { HsvChannel::Value };
+                    apply_nudge(channel, (-dy).round() as i32 * step);
+                }
+                // Seitwärts, z.B. Neigerad oder Touchpad: Sättigung
+                if dx != 0.0 {
+                    apply_nudge(HsvChannel::Saturation, dx.round() as i32 * step);
                 }
+                glib::Propagation::Stop
             });
+            drawing.add_controller(scroll);
         }
-        drawing.add_controller(motion_controller);
 
-        let click = gtk4::GestureClick::new();
-        click.set_button(1);
+        drawing.set_visible(true);
+        hsv_box.append(&drawing);
+        hsv_box.append(&build_hsv_fields(&color_rc, &views));
+        hsv_box.set_visible(true);
 
-        let drawing_for_pressed = drawing.clone();
-        let drawing_for_released = drawing.clone();
+        stack.add_titled(&rgb_box, Some("rgb"), "RGB");
+        stack.add_titled(&cmy_box, Some("cmy"), "CMY");
+        stack.add_titled(&hsv_box, Some("hsv"), "HSV");
 
-        let pa = point_a_for_click.clone();
-        let pb = point_b_for_click.clone();
-        let pc = point_c_for_click.clone();
+        // Weißtöne über Farbtemperatur, z.B. 2700 K Warmweiß oder 5600 K Tageslicht
+        let cct_box = GtkBox::new(gtk::Orientation::Vertical, 4);
+        build_cct_page(&cct_box, &color_rc, &views);
+        stack.add_titled(&cct_box, Some("cct"), "CCT");
+        stack.set_visible(true);
 
-        let left_button_pressed_pressed = left_button_pressed.clone();
-        let left_button_pressed_released = left_button_pressed.clone();
+        let switcher = gtk::StackSwitcher::new();
+        switcher.set_stack(Some(&stack));
+        switcher.set_hexpand(true);
+        let header = GtkBox::new(gtk::Orientation::Horizontal, 8);
+        header.append(&switcher);
+        header.append(&sixteen_bit);
+        root.append(&header);
+        root.append(&stack);
+        root.set_parent(self);
 
-        let mouse_angle_pressed = mouse_angle.clone();
-        let mouse_angle_released = mouse_angle.clone();
+        {
+            let picker = self.downgrade();
+            sixteen_bit.connect_toggled(move |toggle| {
+                if let Some(picker) = picker.upgrade() {
+                    picker.set_bit_depth(if toggle.is_active() { 16 } else { 8 });
+                }
+            });
+        }
 
-        let circle_radius_pressed = circle_radius.clone();
-        let circle_radius_released = circle_radius.clone();
+        let _ = imp.widgets.set(Widgets {
+            root,
+            stack,
+            rgb_page: rgb_box,
+            cmy_page: cmy_box,
+            sixteen_bit,
+        });
+    }
+}
 
-        click.connect_pressed(move |_, _, x, y| {
-            let cx = drawing_for_pressed.width() as f64 / 2.0;
-            let cy = drawing_for_pressed.height() as f64 / 2.0;
-            let dx = x - cx;
-            let dy = y - cy;
-            let distance = (dx*dx + dy*dy).sqrt();
-            let angle = dx.atan2(-dy);
+/// Was eine Taste oder das Mausrad verstellt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HsvChannel {
+    Hue,
+    Saturation,
+    Value,
+}
 
-            if distance >= circle_radius_pressed.get() {
-                *mouse_angle_pressed.borrow_mut() = angle;
-                *left_button_pressed_pressed.borrow_mut() = true;
-            }
+/// Schrittweite in `Color`-Einheiten: mit Strg ein Reglerschritt (8 oder 16 bit), mit Umschalt 10 %, sonst 1 %.
+fn nudge_step(state: gdk::ModifierType, sixteen_bit: bool) -> i32 {
+    if state.contains(gdk::ModifierType::CONTROL_MASK) {
+        if sixteen_bit { 1 } else { 257 }
+    } else if state.contains(gdk::ModifierType::SHIFT_MASK) {
+        u16::MAX as i32 / 10
+    } else {
+        u16::MAX as i32 / 100
+    }
+}
 
-            let pa = pa.borrow();
-            let pb = pb.borrow();
-            let pc = pc.borrow();
+/// Verschiebt einen HSV-Kanal um `delta`. Der Farbton läuft im Kreis weiter, Sättigung und Helligkeit bleiben im Bereich.
+fn nudge(color: &mut Color, channel: HsvChannel, delta: i32) {
+    let clamp = |value: u16| (value as i32 + delta).clamp(0, u16::MAX as i32) as u16;
+    let (hue, saturation, value) = (color.hue(), color.saturation(), color.value());
+    match channel {
+        HsvChannel::Hue => {
+            let hue = (hue as i32 + delta).rem_euclid(u16::MAX as i32 + 1) as u16;
+            color.set_hsv(hue, saturation, value);
+        }
+        HsvChannel::Saturation => color.set_hsv(hue, clamp(saturation), value),
+        HsvChannel::Value => color.set_hsv(hue, saturation, clamp(value)),
+    }
+}
 
-            let distance_a = calculate_distance((x,y),(pa.0,pa.1));//((x - pa.0).powi(2) + (y - pa.1).powi(2)).sqrt();
-            let distance_b = calculate_distance((x,y),(pb.0,pb.1));//((x - pb.0).powi(2) + (y - pb.1).powi(2)).sqrt();
-            let distance_c = calculate_distance((x,y),(pc.0,pc.1));//((x - pc.0).powi(2) + (y - pc.1).powi(2)).sqrt();
+/// Breite des Farbton-Rings in Pixeln.
+const RING_WIDTH: f64 = 10.0;
+
+/// Lage von Ring und Dreieck, allein aus Widget-Größe und Farbton berechnet.
+/// Zeichnen und Maus-Handler rechnen sie jeweils selbst aus, statt sich Werte zu hinterlegen.
+struct WheelGeometry {
+    center: Point,
+    /// Mitte des Rings.
+    ring_radius: f64,
+    /// Ab diesem Abstand zur Mitte zählt ein Klick zum Ring.
+    ring_inner_radius: f64,
+    /// Reiner Farbton, Weiß, Schwarz.
+    triangle: [Point; 3],
+}
 
-            //println!("{}", distance_a);
-            //println!("{}", distance_b);
-            //println!("{}", distance_c);
-            //println!("---");
+impl WheelGeometry {
+    fn new(width: i32, height: i32, color: &Color) -> Self {
+        let center = (width as f64 / 2.0, height as f64 / 2.0);
+        let ring_radius = center.0.min(center.1) - RING_WIDTH;
+        let (hue, _, _) = color.map_hsv_to_unit();
+        Self {
+            center,
+            ring_radius,
+            ring_inner_radius: ring_radius - RING_WIDTH / 1.50,
+            triangle: wheel_raster::triangle_points(center, ring_radius - RING_WIDTH, hue_angle(hue as f64)),
+        }
+    }
+}
 
-            drawing_for_pressed.queue_draw();
-            //println!("Abstand zur Mitte: {:.2}", distance);
-        });
+/// Drehwinkel zu einem Farbton 0..1, im Uhrzeigersinn ab oben.
+fn hue_angle(hue: f64) -> f64 {
+    hue * 2.0 * std::f64::consts::PI
+}
 
-        let pa = point_a_for_click.clone();
-        let pb = point_b_for_click.clone();
-        let pc = point_c_for_click.clone();
-
-        click.connect_released(move |_, _, x, y| {
-            let cx = drawing_for_released.width() as f64 / 2.0;
-            let cy = drawing_for_released.height() as f64 / 2.0;
-            let dx = x - cx;
-            let dy = y - cy;
-            let distance = (dx*dx + dy*dy).sqrt();
-            let angle = dx.atan2(-dy);
-
-            if distance >= circle_radius_released.get() || *left_button_pressed_released.borrow() {
-                *mouse_angle_released.borrow_mut() = angle;
-                *left_button_pressed_released.borrow_mut() = false;
-            }
+/// Malt Ring, Dreieck und die beiden Markierungen für `color`.
+///
+/// Hängt nur von `color` und der Größe ab, `cache` spart lediglich das erneute Rastern.
+fn draw_wheel(cr: &cairo::Context, width: i32, height: i32, color: &Color, cache: &mut WheelCache) {
+    let geometry = WheelGeometry::new(width, height, color);
+    let (cx, cy) = geometry.center;
+    let radius = geometry.ring_radius;
+    let size = (width, height);
+
+    if cache.ring.as_ref().map(|(cached_size, _)| *cached_size) != Some(size) {
+        cache.ring = Some((size, render_ring(width, height, radius, RING_WIDTH)));
+    }
+    if let Some((_, ring)) = &cache.ring {
+        cr.set_source_surface(ring, 0.0, 0.0).unwrap();
+        cr.paint().unwrap();
+    }
 
-            let pa = pa.borrow();
-            let pb = pb.borrow();
-            let pc = pc.borrow();
+    let (hue, saturation, value) = color.map_hsv_to_unit();
+    let (hue, saturation, value) = (hue as f64, saturation as f64, value as f64);
+    let points = geometry.triangle;
 
-            let distance_a = calculate_distance((x,y),(pa.0,pa.1));//((x - pa.0).powi(2) + (y - pa.1).powi(2)).sqrt();
-            let distance_b = calculate_distance((x,y),(pb.0,pb.1));//((x - pb.0).powi(2) + (y - pb.1).powi(2)).sqrt();
-            let distance_c = calculate_distance((x,y),(pc.0,pc.1));//((x - pc.0).powi(2) + (y - pc.1).powi(2)).sqrt();
+    // Das Dreieck dreht sich mit dem Farbton, also muss es nur bei neuem Farbton neu gerastert werden
+    let key = (width, height, color.hue());
+    if cache.triangle.as_ref().map(|(cached_key, _, _)| *cached_key) != Some(key) {
+        let (rect, surface) = render_triangle(points, hsv_to_rgb(hue, 1.0, 1.0));
+        cache.triangle = Some((key, rect, surface));
+    }
+    if let Some((_, rect, triangle)) = &cache.triangle {
+        cr.set_source_surface(triangle, rect.x as f64, rect.y as f64).unwrap();
+        cr.paint().unwrap();
+    }
 
-            //println!("A: {}", distance_a);
-            //println!("B: {}", distance_b);
-            //println!("C: {}", distance_c);
-            //println!("---");
+    // Markierung auf dem Ring am aktuellen Farbton, schwarz-weiß damit sie auf jeder Farbe sichtbar ist
+    let handle_angle = hue_angle(hue) - std::f64::consts::PI / 2.0;
+    let (hx, hy) = (cx + radius * handle_angle.cos(), cy + radius * handle_angle.sin());
+    cr.new_path();
+    cr.arc(hx, hy, RING_WIDTH, 0.0, 2.0 * std::f64::consts::PI);
+    cr.set_source_rgb(0.0, 0.0, 0.0);
+    cr.set_line_width(3.0);
+    cr.stroke_preserve().unwrap();
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.set_line_width(1.5);
+    cr.stroke().unwrap();
+
+    // Markierung an der aktuellen Sättigung/Helligkeit
+    let (mx, my) = sv_to_triangle(saturation, value, points[0], points[1], points[2]);
+    let contrast = if value > 0.5 { 0.0 } else { 1.0 };
+    cr.new_path();
+    cr.arc(mx, my, 6.0, 0.0, 2.0 * std::f64::consts::PI);
+    cr.set_source_rgb(contrast, contrast, contrast);
+    cr.set_line_width(2.0);
+    cr.stroke().unwrap();
+}
 
-            drawing_for_released.queue_draw();
-            //println!("Losgelassen, Abstand: {:.2}", distance);
-        });
-        drawing.add_controller(click);
+/// Malt den Farbton-Ring in ein eigenes Surface, damit er zwischengespeichert werden kann.
+fn render_ring(width: i32, height: i32, radius: f64, circle_width: f64) -> cairo::ImageSurface {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+    let cr = cairo::Context::new(&surface).unwrap();
+    let cx = width as f64 / 2.0;
+    let cy = height as f64 / 2.0;
+
+    let offset = -std::f64::consts::PI / 2.0;
+    cr.set_line_width(circle_width * 2.0);
+    for i in 0..360 {
+        let angle1 = (i as f64).to_radians() + offset - 0.005;
+        let angle2 = ((i + 1) as f64).to_radians() + offset;
+        let (r, g, b) = hsv_to_rgb(i as f64 / 360.0, 1.0, 1.0);
+        cr.set_source_rgb(r, g, b);
+        cr.arc(cx, cy, radius, angle1, angle2);
+        cr.stroke().unwrap();
+    }
+    drop(cr);
+    surface
+}
 
-        let drag = gtk4::GestureDrag::new();
+/// Rastert das Dreieck in ein Surface, das nur seine Bounding Box abdeckt.
+fn render_triangle(points: [Point; 3], hue_rgb: (f64, f64, f64)) -> (PixelRect, cairo::ImageSurface) {
+    let rect = wheel_raster::bounding_box(points);
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, rect.width, rect.height).unwrap();
+    let stride = surface.stride() as usize;
+    wheel_raster::rasterize_triangle(&mut surface.data().unwrap(), stride, rect, points, hue_rgb);
+    (rect, surface)
+}
 
-        let drag_start = Rc::new(RefCell::new((0.0_f64, 0.0_f64)));
+/// Baut eine Seite mit drei Schiebereglern samt Zahlenfeld, z.B. R/G/B oder C/M/Y,
+/// wahlweise in 8 bit (0..=255) oder 16 bit (0..=65535).
+/// `read` holt die drei Werte aus der Farbe, `write` setzt sie.
+fn build_channel_page(
+    page: &GtkBox,
+    labels: [&str; 3],
+    read: fn(&Color) -> [u16; 3],
+    write: fn(&mut Color, [u16; 3]),
+    sixteen_bit: &gtk::CheckButton,
+    color: &Rc<RefCell<Color>>,
+    views: &Rc<Views>,
+) {
+    let adjustments = labels.map(|label| channel_row(page, label));
+
+    let values = read(&color.borrow());
+    for (adjustment, value) in adjustments.iter().zip(values) {
+        adjustment.set_value(from_u16(value, sixteen_bit.is_active()));
+    }
 
-        drag.connect_drag_begin({
-            let drag_start = drag_start.clone();
-            move |_, x, y| {
-                // x,y sind HIER absolut
-                *drag_start.borrow_mut() = (x, y);
+    for (index, adjustment) in adjustments.iter().enumerate() {
+        let sixteen_bit = sixteen_bit.clone();
+        let color = color.clone();
+        let views = views.clone();
+        adjustment.connect_value_changed(move |adjustment| {
+            if views.is_updating() {
+                return;
             }
+            // Nur den geänderten Kanal übernehmen, die anderen Regler sind in 8 bit gerundet
+            let mut values = read(&color.borrow());
+            values[index] = to_u16(adjustment.value(), sixteen_bit.is_active());
+            write(&mut color.borrow_mut(), values);
+            let c = *color.borrow();
+            views.notify(&c);
         });
+    }
 
-        drag.connect_drag_update({
-            let drag = gtk4::GestureDrag::new();
+    // Regler folgen Änderungen aus dem Rad und den anderen Seiten
+    {
+        let adjustments = adjustments.clone();
+        let sixteen_bit = sixteen_bit.clone();
+        views.subscribe(move |c| {
+            for (adjustment, value) in adjustments.iter().zip(read(c)) {
+                adjustment.set_value(from_u16(value, sixteen_bit.is_active()));
+            }
+        });
+    }
 
-            let drag_start = drag_start.clone();
-            let pa = point_a_for_click.clone();
-            let pb = point_b_for_click.clone();
-            let pc = point_c_for_click.clone();
-            let color_rc_clone = color_rc.clone(); // 🔹 Clone vor der Closure
+    {
+        let color = color.clone();
+        let views = views.clone();
+        sixteen_bit.connect_toggled(move |toggle| {
+            let upper = if toggle.is_active() { u16::MAX as f64 } else { u8::MAX as f64 };
+            let values = read(&color.borrow());
+            let was_updating = views.updating.replace(true);
+            for (adjustment, value) in adjustments.iter().zip(values) {
+                adjustment.set_upper(upper);
+                adjustment.set_value(from_u16(value, toggle.is_active()));
+            }
+            views.updating.set(was_updating);
+        });
+    }
+}
 
-            drag.connect_drag_update(move |_, dx, dy| {
-                // ======================
-                // Drag-Position
-                // ======================
-                let (sx, sy) = *drag_start.borrow();
-                let x = sx + dx;
-                let y = sy + dy;
-
-                // ======================
-                // Punkte EINMAL borrowen
-                // ======================
-                let (pax, pay) = *pa.borrow();
-                let (pbx, pby) = *pb.borrow();
-                let (pcx, pcy) = *pc.borrow();
-
-                const MAX_ANGLE: f64 = std::f64::consts::PI / 3.0;
-
-                // VALUE
-                let vec_b_a = (x - pbx, y - pby);
-                let vec_b_c = (pcx - pbx, pcy - pby);
-                let dot_val = vec_b_a.0 * vec_b_c.0 + vec_b_a.1 * vec_b_c.1;
-                let len_ba = calculate_distance((x, y), (pbx, pby));
-                let len_bc = calculate_distance((pbx, pby), (pcx, pcy));
-                if len_ba < 1e-6 || len_bc < 1e-6 { return; }
-                let mut cos_val = dot_val / (len_ba * len_bc);
-                cos_val = cos_val.clamp(-1.0, 1.0);
-                let value_angle = cos_val.acos();
-                let value = (value_angle / MAX_ANGLE).clamp(0.0, 1.0);
-
-                // SATURATION
-                let vec_c_a = (x - pcx, y - pcy);
-                let vec_c_b = (pbx - pcx, pby - pcy);
-                let dot_sat = vec_c_a.0 * vec_c_b.0 + vec_c_a.1 * vec_c_b.1;
-                let len_ca = calculate_distance((x, y), (pcx, pcy));
-                let len_cb = calculate_distance((pbx, pby), (pcx, pcy));
-                if len_ca < 1e-6 || len_cb < 1e-6 { return; }
-                let mut cos_sat = dot_sat / (len_ca * len_cb);
-                cos_sat = cos_sat.clamp(-1.0, 1.0);
-                let saturation_angle = cos_sat.acos();
-                let saturation = (saturation_angle / MAX_ANGLE).clamp(0.0, 1.0);
-
-                // COLOR
-                {
-                    let mut c = color_rc_clone.borrow_mut(); // 🔹 direkt clone benutzen
-                    c.set_saturation(map(saturation as f32, 0.0, 1.0, 0.0, u16::MAX as f32) as u16);
-                    c.set_value(map(value as f32, 0.0, 1.0, 0.0, u16::MAX as f32) as u16);
-                }
+/// Zahlenfelder für Farbton in Grad sowie Sättigung und Helligkeit in Prozent.
+/// Als SpinButtons sind sie auch für Screenreader normale Wertebereiche.
+fn build_hsv_fields(color: &Rc<RefCell<Color>>, views: &Rc<Views>) -> GtkBox {
+    let fields = GtkBox::new(gtk::Orientation::Vertical, 4);
+    fields.set_valign(gtk::Align::Center);
+    let adjustments = [
+        hsv_field(&fields, "H", "Farbton in Grad", 360.0),
+        hsv_field(&fields, "S", "Sättigung in Prozent", 100.0),
+        hsv_field(&fields, "V", "Helligkeit in Prozent", 100.0),
+    ];
+
+    let show = {
+        let adjustments = adjustments.clone();
+        move |c: &Color| {
+            let (hue, saturation, value) = c.map_hsv_to_unit();
+            adjustments[0].set_value((hue as f64 * 360.0).round());
+            adjustments[1].set_value((saturation as f64 * 100.0).round());
+            adjustments[2].set_value((value as f64 * 100.0).round());
+        }
+    };
+    show(&color.borrow());
+    views.subscribe(show);
+
+    for (index, adjustment) in adjustments.iter().enumerate() {
+        let color = color.clone();
+        let views = views.clone();
+        adjustment.connect_value_changed(move |adjustment| {
+            if views.is_updating() {
+                return;
+            }
+            // Nur den geänderten Kanal übernehmen, die anderen Felder sind gerundet
+            let mut hsv = {
+                let c = color.borrow();
+                [c.hue(), c.saturation(), c.value()]
+            };
+            hsv[index] = ((adjustment.value() / adjustment.upper()).clamp(0.0, 1.0) * u16::MAX as f64).round() as u16;
+            color.borrow_mut().set_hsv(hsv[0], hsv[1], hsv[2]);
+            let c = *color.borrow();
+            views.notify(&c);
+        });
+    }
+    fields
+}
 
-                println!("value      : {:.4}", value);
-                println!("saturation : {:.4}", saturation);
-                println!("---");
-            });
+fn hsv_field(fields: &GtkBox, label: &str, accessible_label: &str, upper: f64) -> gtk::Adjustment {
+    let row = GtkBox::new(gtk::Orientation::Horizontal, 4);
+    let adjustment = gtk::Adjustment::new(0.0, 0.0, upper, 1.0, 10.0, 0.0);
 
-            drawing.add_controller(drag);
+    let label = gtk::Label::new(Some(label));
+    label.set_width_chars(2);
+    row.append(&label);
 
+    let spin = gtk::SpinButton::new(Some(&adjustment), 1.0, 0);
+    spin.update_property(&[gtk::accessible::Property::Label(accessible_label)]);
+    row.append(&spin);
+
+    fields.append(&row);
+    adjustment
+}
+
+/// Farbtemperatur und Tint (Duv). Die Helligkeit bleibt dabei erhalten, Schwarz wird zu vollem Weiß.
+fn build_cct_page(page: &GtkBox, color: &Rc<RefCell<Color>>, views: &Rc<Views>) {
+    let temperature = gtk::Adjustment::new(
+        6500.0,
+        cct::KELVIN_MIN as f64,
+        cct::KELVIN_MAX as f64,
+        50.0,
+        500.0,
+        0.0,
+    );
+    slider_row(page, "K", &temperature, 0);
+    let tint = gtk::Adjustment::new(0.0, -MAX_TINT, MAX_TINT, 0.001, 0.005, 0.0);
+    slider_row(page, "Tint", &tint, 3);
+
+    // Eigene Änderungen nicht aus der Farbe zurücklesen, sonst springt der Regler beim Ziehen um Rundungsfehler
+    let writing = Rc::new(Cell::new(false));
+
+    let show = {
+        let temperature = temperature.clone();
+        let tint = tint.clone();
+        let writing = writing.clone();
+        move |c: &Color| {
+            if writing.get() {
+                return;
+            }
+            if let Some((kelvin, duv)) = c.cct() {
+                temperature.set_value(kelvin as f64);
+                tint.set_value((duv as f64).clamp(-MAX_TINT, MAX_TINT));
+            }
+        }
+    };
+    views.subscribe(show);
+
+    for adjustment in [&temperature, &tint] {
+        let temperature = temperature.clone();
+        let tint = tint.clone();
+        let color = color.clone();
+        let views = views.clone();
+        let writing = writing.clone();
+        adjustment.connect_value_changed(move |_| {
+            if views.is_updating() {
+                return;
+            }
+            color.borrow_mut().set_cct(temperature.value().round() as u16, tint.value() as f32);
+            let c = *color.borrow();
+            let was_writing = writing.replace(true);
+            views.notify(&c);
+            writing.set(was_writing);
         });
+    }
+}
 
+/// Größter Tint-Betrag auf der CCT-Seite. Weiter weg ist es kein Weiß mehr.
+const MAX_TINT: f64 = 0.02;
 
-        drawing.add_controller(drag);
+/// Eine Zeile aus Beschriftung, Schieberegler und Zahlenfeld, die sich ein Adjustment teilen.
+fn channel_row(page: &GtkBox, label: &str) -> gtk::Adjustment {
+    let adjustment = gtk::Adjustment::new(0.0, 0.0, u8::MAX as f64, 1.0, 16.0, 0.0);
+    slider_row(page, label, &adjustment, 0);
+    adjustment
+}
 
-        drawing.set_visible(true);
-        hsv_box.append(&drawing);
-        hsv_box.set_visible(true);
+fn slider_row(page: &GtkBox, label: &str, adjustment: &gtk::Adjustment, digits: u32) {
+    let row = GtkBox::new(gtk::Orientation::Horizontal, 4);
 
-        stack.add_titled(&rgb_box, Some("rgb"), "RGB");
-        stack.add_titled(&cmy_box, Some("cmy"), "CMY");
-        stack.add_titled(&hsv_box, Some("hsv"), "HSV");
-        stack.set_visible(true);
+    let label = gtk::Label::new(Some(label));
+    label.set_width_chars(4);
+    row.append(&label);
 
-        let switcher = gtk::StackSwitcher::new();
-        switcher.set_stack(Some(&stack));
-        root.append(&switcher);
-        root.append(&stack);
+    let scale = gtk::Scale::new(gtk::Orientation::Horizontal, Some(adjustment));
+    scale.set_digits(digits as i32);
+    scale.set_hexpand(true);
+    row.append(&scale);
+
+    let spin = gtk::SpinButton::new(Some(adjustment), 1.0, digits);
+    row.append(&spin);
 
-        Self {root, color}
+    page.append(&row);
+}
+
+/// Reglerwert (8 oder 16 bit) auf den vollen u16-Bereich von `Color`.
+fn to_u16(value: f64, sixteen_bit: bool) -> u16 {
+    if sixteen_bit {
+        value.round() as u16
+    } else {
+        (value.round() as u16).saturating_mul(257)
     }
+}
 
-    pub fn widget(&self) -> &GtkBox {
-        &self.root
+/// `Color`-Wert auf den Reglerbereich, bei 8 bit gerundet.
+fn from_u16(value: u16, sixteen_bit: bool) -> f64 {
+    if sixteen_bit {
+        value as f64
+    } else {
+        (value as f64 / 257.0).round()
     }
 }
 
@@ -375,6 +857,69 @@ pub fn map(x: f32, in_min: f32, in_max: f32, out_min: f32, out_max: f32) -> f32
     (x - in_min) / (in_max - in_min) * (out_max - out_min) + out_min
 }
 
+/// Sättigung und Helligkeit zu einem Punkt im HSV-Dreieck mit den Ecken reiner Farbton,
+/// Weiß und Schwarz. Punkte außerhalb werden auf den nächsten Rand gezogen.
+///
+/// In Schwarz ist die Sättigung beliebig, dann bleibt `current_saturation` erhalten.
+fn triangle_to_sv(p: Point, hue: Point, white: Point, black: Point, current_saturation: f64) -> (f64, f64) {
+    let p = closest_point_in_triangle(p, hue, white, black);
+    let (w_hue, w_white, _) = barycentric(p, hue, white, black);
+    let w_hue = w_hue.clamp(0.0, 1.0);
+    let w_white = w_white.clamp(0.0, 1.0);
+
+    // Farbe = v * (s * Farbton + (1 - s) * Weiß) + (1 - v) * Schwarz
+    let value = (w_hue + w_white).clamp(0.0, 1.0);
+    let saturation = if value > 1e-6 { (w_hue / value).clamp(0.0, 1.0) } else { current_saturation };
+    (saturation, value)
+}
+
+/// Umkehrung von [`triangle_to_sv`]: wo eine Farbe im Dreieck liegt.
+fn sv_to_triangle(saturation: f64, value: f64, hue: Point, white: Point, black: Point) -> Point {
+    let w_hue = value * saturation;
+    let w_white = value * (1.0 - saturation);
+    let w_black = 1.0 - value;
+    (
+        w_hue * hue.0 + w_white * white.0 + w_black * black.0,
+        w_hue * hue.1 + w_white * white.1 + w_black * black.1,
+    )
+}
+
+/// Baryzentrische Koordinaten von `p` bezüglich des Dreiecks `a`, `b`, `c`.
+fn barycentric(p: Point, a: Point, b: Point, c: Point) -> (f64, f64, f64) {
+    let denom = (b.1 - c.1) * (a.0 - c.0) + (c.0 - b.0) * (a.1 - c.1);
+    if denom.abs() < 1e-12 {
+        return (1.0, 0.0, 0.0);
+    }
+    let w_a = ((b.1 - c.1) * (p.0 - c.0) + (c.0 - b.0) * (p.1 - c.1)) / denom;
+    let w_b = ((c.1 - a.1) * (p.0 - c.0) + (a.0 - c.0) * (p.1 - c.1)) / denom;
+    (w_a, w_b, 1.0 - w_a - w_b)
+}
+
+fn closest_point_in_triangle(p: Point, a: Point, b: Point, c: Point) -> Point {
+    let (w_a, w_b, w_c) = barycentric(p, a, b, c);
+    if w_a >= 0.0 && w_b >= 0.0 && w_c >= 0.0 {
+        return p;
+    }
+    [
+        closest_point_on_segment(p, a, b),
+        closest_point_on_segment(p, b, c),
+        closest_point_on_segment(p, c, a),
+    ]
+    .into_iter()
+    .min_by(|q1, q2| calculate_distance(p, *q1).total_cmp(&calculate_distance(p, *q2)))
+    .unwrap()
+}
+
+fn closest_point_on_segment(p: Point, a: Point, b: Point) -> Point {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let length_squared = ab.0 * ab.0 + ab.1 * ab.1;
+    if length_squared < 1e-12 {
+        return a;
+    }
+    let t = (((p.0 - a.0) * ab.0 + (p.1 - a.1) * ab.1) / length_squared).clamp(0.0, 1.0);
+    (a.0 + t * ab.0, a.1 + t * ab.1)
+}
+
 fn calculate_distance(p1: (f64, f64), p2: (f64, f64)) -> f64 {
     let dx = p2.0 - p1.0;
     let dy = p2.1 - p1.1;
@@ -396,4 +941,95 @@ fn hsv_to_rgb(h: f64, s: f64, v: f64) -> (f64, f64, f64) {
         4 => (t, p, v),
         _ => (v, p, q),
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE: [Point; 3] = [(120.0, 20.0), (206.6, 170.0), (33.4, 170.0)];
+
+    #[test]
+    fn nested_notify_keeps_updating() {
+        let views = Rc::new(Views::default());
+        let nested = Rc::new(Cell::new(false));
+        {
+            let views_inner = Rc::downgrade(&views);
+            let nested = nested.clone();
+            views.subscribe(move |color| {
+                // Wie ein `connect_color_changed`-Handler, der `set_color` aufruft
+                if !nested.replace(true) {
+                    views_inner.upgrade().unwrap().notify(color);
+                }
+            });
+        }
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        {
+            let views_inner = Rc::downgrade(&views);
+            let seen = seen.clone();
+            views.subscribe(move |_| seen.borrow_mut().push(views_inner.upgrade().unwrap().is_updating()));
+        }
+
+        views.notify(&Color::new());
+        assert_eq!(*seen.borrow(), [true, true]);
+        assert!(!views.is_updating());
+    }
+
+    #[test]
+    fn redraw_keeps_color() {
+        let mut color = Color::new();
+        color.set_hsv(20000, 40000, 50000);
+        let before = color;
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 240, 240).unwrap();
+        let cr = cairo::Context::new(&surface).unwrap();
+        let mut cache = WheelCache::default();
+        for _ in 0..3 {
+            draw_wheel(&cr, 240, 240, &color, &mut cache);
+            assert_eq!(color, before);
+        }
+        // Andere Größe, also neu gerastert
+        draw_wheel(&cr, 200, 180, &color, &mut cache);
+        assert_eq!(color, before);
+    }
+
+    #[test]
+    fn triangle_round_trip() {
+        let [hue, white, black] = TRIANGLE;
+        for saturation in [0.0, 0.25, 0.5, 1.0] {
+            for value in [0.1, 0.5, 1.0] {
+                let p = sv_to_triangle(saturation, value, hue, white, black);
+                let (s, v) = triangle_to_sv(p, hue, white, black, 0.7);
+                assert!((s - saturation).abs() < 1e-9 && (v - value).abs() < 1e-9, "{:?} statt {:?}", (s, v), (saturation, value));
+            }
+        }
+    }
+
+    #[test]
+    fn triangle_corners() {
+        let [hue, white, black] = TRIANGLE;
+        assert_eq!(triangle_to_sv(hue, hue, white, black, 0.0), (1.0, 1.0));
+        assert_eq!(triangle_to_sv(white, hue, white, black, 0.5), (0.0, 1.0));
+        // In Schwarz bleibt die bisherige Sättigung
+        assert_eq!(triangle_to_sv(black, hue, white, black, 0.7), (0.7, 0.0));
+    }
+
+    #[test]
+    fn outside_points_snap_to_edge() {
+        let [hue, white, black] = TRIANGLE;
+        let (s, v) = triangle_to_sv((120.0, -50.0), hue, white, black, 0.0);
+        assert!((s - 1.0).abs() < 1e-9 && (v - 1.0).abs() < 1e-9);
+        let (s, v) = triangle_to_sv((120.0, 300.0), hue, white, black, 0.0);
+        assert!((0.0..=1.0).contains(&s) && (v - 0.5).abs() < 1e-9, "{:?}", (s, v));
+    }
+
+    #[test]
+    fn slider_values_round_trip() {
+        for value in 0..=255 {
+            assert_eq!(from_u16(to_u16(value as f64, false), false), value as f64);
+        }
+        for value in [0, 1, 256, 32768, u16::MAX] {
+            assert_eq!(to_u16(from_u16(value, true), true), value);
+        }
+        assert_eq!(to_u16(255.0, false), u16::MAX);
+    }
+}
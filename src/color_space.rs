@@ -0,0 +1,210 @@
+//! Umrechnungen zwischen sRGB und CIE XYZ, xyY, L*a*b*, LCh sowie OKLab und OKLCh.
+//!
+//! Arbeitsraum ist sRGB mit Weißpunkt D65. Alle Räume werden aus linearem sRGB (Transferkurve entfernt)
+//! berechnet, L*a*b* bezieht sich ebenfalls auf D65. Winkel (h in LCh/OKLCh) sind in Grad 0..360.
+
+/// Weißpunkt D65 in XYZ, Y = 1.
+pub const D65_WHITE: [f64; 3] = [0.950_47, 1.0, 1.088_83];
+
+/// sRGB-Transferkurve, 0..1 auf linear 0..1.
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Umkehrung von [`srgb_to_linear`].
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn linear_srgb_to_xyz([r, g, b]: [f64; 3]) -> [f64; 3] {
+    [
+        0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+        0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b,
+        0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b,
+    ]
+}
+
+/// Kann außerhalb von 0..1 liegen, wenn die Farbe nicht in sRGB darstellbar ist.
+pub fn xyz_to_linear_srgb([x, y, z]: [f64; 3]) -> [f64; 3] {
+    [
+        3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+        -0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z,
+        0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+    ]
+}
+
+/// Farbort x, y mit Helligkeit `luminance` (Y) nach XYZ.
+pub fn xy_to_xyz((x, y): (f64, f64), luminance: f64) -> [f64; 3] {
+    if y <= 0.0 {
+        return [0.0, 0.0, 0.0];
+    }
+    [x * luminance / y, luminance, (1.0 - x - y) * luminance / y]
+}
+
+/// Farbort x, y aus XYZ, `None` für Schwarz.
+pub fn xyz_to_xy([x, y, z]: [f64; 3]) -> Option<(f64, f64)> {
+    let sum = x + y + z;
+    if sum <= 1e-12 {
+        return None;
+    }
+    Some((x / sum, y / sum))
+}
+
+/// xyY: Farbort x, y und Helligkeit Y. Schwarz bekommt den Farbort von D65.
+pub fn xyz_to_xyy(xyz: [f64; 3]) -> [f64; 3] {
+    let (x, y) = xyz_to_xy(xyz).unwrap_or_else(|| xyz_to_xy(D65_WHITE).unwrap());
+    [x, y, xyz[1]]
+}
+
+pub fn xyy_to_xyz([x, y, luminance]: [f64; 3]) -> [f64; 3] {
+    xy_to_xyz((x, y), luminance)
+}
+
+const LAB_EPSILON: f64 = 6.0 / 29.0;
+
+fn lab_f(t: f64) -> f64 {
+    if t > LAB_EPSILON.powi(3) {
+        t.cbrt()
+    } else {
+        t / (3.0 * LAB_EPSILON * LAB_EPSILON) + 4.0 / 29.0
+    }
+}
+
+fn lab_f_inverse(t: f64) -> f64 {
+    if t > LAB_EPSILON {
+        t.powi(3)
+    } else {
+        3.0 * LAB_EPSILON * LAB_EPSILON * (t - 4.0 / 29.0)
+    }
+}
+
+/// CIE 1976 L*a*b*, L 0..100.
+pub fn xyz_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let [fx, fy, fz] = [0, 1, 2].map(|i| lab_f(xyz[i] / D65_WHITE[i]));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub fn lab_to_xyz([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let fy = (l + 16.0) / 116.0;
+    let f = [fy + a / 500.0, fy, fy - b / 200.0];
+    [0, 1, 2].map(|i| lab_f_inverse(f[i]) * D65_WHITE[i])
+}
+
+/// Kartesisch (L, a, b) nach polar (L, Chroma, Farbton in Grad). Gilt für L*a*b* und OKLab.
+pub fn to_polar([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let chroma = (a * a + b * b).sqrt();
+    let hue = b.atan2(a).to_degrees().rem_euclid(360.0);
+    [l, chroma, hue]
+}
+
+pub fn from_polar([l, chroma, hue]: [f64; 3]) -> [f64; 3] {
+    let (sin, cos) = hue.to_radians().sin_cos();
+    [l, chroma * cos, chroma * sin]
+}
+
+/// OKLab nach Björn Ottosson, L 0..1.
+pub fn linear_srgb_to_oklab([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+    let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+    let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+    [
+        0.210_454_255_3 * l + 0.793_617_785_0 * m - 0.004_072_046_8 * s,
+        1.977_998_495_1 * l - 2.428_592_205_0 * m + 0.450_593_709_9 * s,
+        0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766_0 * s,
+    ]
+}
+
+pub fn oklab_to_linear_srgb([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let l_ = (l + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+    let m_ = (l - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+    let s_ = (l - 0.089_484_177_5 * a - 1.291_485_548_0 * b).powi(3);
+    [
+        4.076_741_662_1 * l_ - 3.307_711_591_3 * m_ + 0.230_969_929_2 * s_,
+        -1.268_438_004_6 * l_ + 2.609_757_401_1 * m_ - 0.341_319_396_5 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_614_7 * m_ + 1.707_614_701_0 * s_,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [[f64; 3]; 6] = [
+        [1.0, 1.0, 1.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+        [0.8, 0.35, 0.05],
+        [0.02, 0.2, 0.6],
+    ];
+
+    fn assert_close(actual: [f64; 3], expected: [f64; 3], tolerance: f64) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() <= tolerance, "{:?} statt {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn transfer_curve_round_trip() {
+        for c in [0.0, 0.002, 0.04, 0.2, 0.5, 1.0] {
+            assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn xyz_round_trip() {
+        for rgb in SAMPLES {
+            assert_close(xyz_to_linear_srgb(linear_srgb_to_xyz(rgb)), rgb, 1e-6);
+        }
+    }
+
+    #[test]
+    fn xyy_round_trip() {
+        for rgb in SAMPLES {
+            let xyz = linear_srgb_to_xyz(rgb);
+            assert_close(xyy_to_xyz(xyz_to_xyy(xyz)), xyz, 1e-12);
+        }
+    }
+
+    #[test]
+    fn lab_and_lch_round_trip() {
+        for rgb in SAMPLES {
+            let xyz = linear_srgb_to_xyz(rgb);
+            let lab = xyz_to_lab(xyz);
+            assert_close(lab_to_xyz(lab), xyz, 1e-12);
+            assert_close(from_polar(to_polar(lab)), lab, 1e-9);
+        }
+    }
+
+    #[test]
+    fn oklab_and_oklch_round_trip() {
+        for rgb in SAMPLES {
+            let oklab = linear_srgb_to_oklab(rgb);
+            assert_close(oklab_to_linear_srgb(oklab), rgb, 1e-6);
+            assert_close(from_polar(to_polar(oklab)), oklab, 1e-12);
+        }
+    }
+
+    #[test]
+    fn white_is_d65() {
+        let xyz = linear_srgb_to_xyz([1.0, 1.0, 1.0]);
+        assert_close(xyz, D65_WHITE, 1e-4);
+        assert_close(xyz_to_lab(xyz), [100.0, 0.0, 0.0], 1e-2);
+        assert_close(linear_srgb_to_oklab([1.0, 1.0, 1.0]), [1.0, 0.0, 0.0], 1e-4);
+    }
+
+    #[test]
+    fn black_has_d65_chromaticity() {
+        let [x, y, luminance] = xyz_to_xyy([0.0, 0.0, 0.0]);
+        let (white_x, white_y) = xyz_to_xy(D65_WHITE).unwrap();
+        assert_close([x, y, luminance], [white_x, white_y, 0.0], 1e-12);
+    }
+}
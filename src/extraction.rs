@@ -0,0 +1,181 @@
+use crate::color::Color;
+
+/// Wie viel Rot, Grün und Blau eine Amber-LED bei voller Helligkeit ersetzt.
+pub const DEFAULT_AMBER: [f32; 3] = [1.0, 0.45, 0.0];
+/// Wie viel Rot, Grün und Blau eine UV-LED ersetzt. Sichtbar ist davon vor allem ein tiefes Violett.
+pub const DEFAULT_UV: [f32; 3] = [0.25, 0.0, 1.0];
+
+/// Wie der Weißanteil einer Farbe auf die weiße LED kommt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteStrategy {
+    /// `min(R, G, B)` geht auf Weiß und wird von RGB abgezogen. Passt, wenn die weiße LED
+    /// ungefähr so aussieht wie R, G und B zusammen auf voll.
+    MinSubtraction,
+    /// Die weiße LED ersetzt diese RGB-Anteile, z.B. `[1.0, 0.8, 0.55]` für Warmweiß.
+    /// So bleibt der Farbton gleich, auch wenn Weiß deutlich wärmer oder kälter ist als RGB.
+    Calibrated([f32; 3]),
+}
+
+impl WhiteStrategy {
+    /// Kalibriert auf eine weiße LED mit bekannter Farbtemperatur.
+    pub fn calibrated_kelvin(kelvin: u16) -> Self {
+        let mut white = Color::new();
+        white.set_kelvin(kelvin);
+        let (r, g, b) = white.map_rgb_to_unit();
+        WhiteStrategy::Calibrated([r, g, b])
+    }
+}
+
+/// Einstellung pro Lampe, wie RGB auf zusätzliche Emitter verteilt wird.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extraction {
+    pub white: WhiteStrategy,
+    pub amber: [f32; 3],
+    pub uv: [f32; 3],
+}
+
+impl Default for Extraction {
+    fn default() -> Self {
+        Self {
+            white: WhiteStrategy::MinSubtraction,
+            amber: DEFAULT_AMBER,
+            uv: DEFAULT_UV,
+        }
+    }
+}
+
+/// Helligkeit jedes Emitters, 0..1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Emitters {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub white: f32,
+    pub amber: f32,
+    pub uv: f32,
+}
+
+impl Extraction {
+    /// Verteilt `color` auf RGB und die Emitter, die die Lampe hat. Reihenfolge: Weiß, Amber, UV.
+    ///
+    /// Jeder Emitter übernimmt so viel, wie von seinen RGB-Anteilen noch übrig ist, und der Rest bleibt auf RGB.
+    /// Fehlt ein Emitter, bleibt sein Anteil ebenfalls auf RGB.
+    pub fn extract(&self, color: &Color, has_white: bool, has_amber: bool, has_uv: bool) -> Emitters {
+        let (r, g, b) = color.map_rgb_to_unit();
+        let mut rgb = [r, g, b];
+
+        let white = match self.white {
+            WhiteStrategy::MinSubtraction => [1.0, 1.0, 1.0],
+            WhiteStrategy::Calibrated(white) => white,
+        };
+
+        let mut take = |present: bool, emitter: [f32; 3]| {
+            if !present {
+                return 0.0;
+            }
+            let level = replaceable(rgb, emitter);
+            for (channel, share) in rgb.iter_mut().zip(emitter) {
+                *channel = (*channel - level * share).max(0.0);
+            }
+            level
+        };
+        let white = take(has_white, white);
+        let amber = take(has_amber, self.amber);
+        let uv = take(has_uv, self.uv);
+
+        Emitters {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            white,
+            amber,
+            uv,
+        }
+    }
+}
+
+/// Wie hell ein Emitter mit den RGB-Anteilen `emitter` höchstens werden kann, ohne mehr abzuziehen als da ist.
+/// Ein Emitter ohne RGB-Anteile ersetzt nichts und bleibt aus.
+fn replaceable(rgb: [f32; 3], emitter: [f32; 3]) -> f32 {
+    rgb.iter()
+        .zip(emitter)
+        .filter(|(_, share)| *share > 1e-6)
+        .map(|(channel, share)| channel / share)
+        .reduce(f32::min)
+        .map_or(0.0, |level| level.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} statt {}", actual, expected);
+    }
+
+    fn unit_color(r: f32, g: f32, b: f32) -> Color {
+        let to_u16 = |v: f32| (v * u16::MAX as f32).round() as u16;
+        let mut color = Color::new();
+        color.set_rgb(to_u16(r), to_u16(g), to_u16(b));
+        color
+    }
+
+    #[test]
+    fn min_subtraction_on_pastel() {
+        let emitters = Extraction::default().extract(&unit_color(1.0, 0.8, 0.6), true, false, false);
+        assert_near(emitters.white, 0.6);
+        assert_near(emitters.red, 0.4);
+        assert_near(emitters.green, 0.2);
+        assert_near(emitters.blue, 0.0);
+    }
+
+    #[test]
+    fn calibrated_white_takes_its_own_color() {
+        let mut warm = Color::new();
+        warm.set_kelvin(2700);
+        let extraction = Extraction { white: WhiteStrategy::calibrated_kelvin(2700), ..Extraction::default() };
+        let emitters = extraction.extract(&warm, true, false, false);
+        assert_near(emitters.white, 1.0);
+        assert_near(emitters.red, 0.0);
+        assert_near(emitters.green, 0.0);
+        assert_near(emitters.blue, 0.0);
+    }
+
+    #[test]
+    fn white_before_amber_before_uv() {
+        let extraction = Extraction::default();
+        let white = unit_color(1.0, 1.0, 1.0);
+
+        // Weiß kommt zuerst und lässt für Amber und UV nichts übrig
+        let emitters = extraction.extract(&white, true, true, true);
+        assert_near(emitters.white, 1.0);
+        assert_near(emitters.amber, 0.0);
+        assert_near(emitters.uv, 0.0);
+
+        // Ohne Weiß nimmt Amber das ganze Rot, für UV bleibt kein Rot mehr
+        let emitters = extraction.extract(&white, false, true, true);
+        assert_near(emitters.amber, 1.0);
+        assert_near(emitters.uv, 0.0);
+        assert_near(emitters.red, 0.0);
+        assert_near(emitters.green, 0.55);
+        assert_near(emitters.blue, 1.0);
+    }
+
+    #[test]
+    fn missing_emitters_stay_on_rgb() {
+        let emitters = Extraction::default().extract(&unit_color(1.0, 0.8, 0.6), false, false, false);
+        assert_eq!(emitters.white, 0.0);
+        assert_near(emitters.red, 1.0);
+        assert_near(emitters.green, 0.8);
+        assert_near(emitters.blue, 0.6);
+    }
+
+    #[test]
+    fn emitter_without_shares_stays_off() {
+        let extraction = Extraction { amber: [0.0; 3], ..Extraction::default() };
+        let emitters = extraction.extract(&unit_color(1.0, 0.5, 0.0), false, true, false);
+        assert_eq!(emitters.amber, 0.0);
+        assert_near(emitters.red, 1.0);
+        assert_near(emitters.green, 0.5);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::art_net_sender::DMX_CHANNELS;
+use crate::color::Color;
+use crate::extraction::{Emitters, Extraction};
+
+/// Farbtemperaturen, die ein CCT-Kanal von 0 bis voll abdeckt. Passt für die meisten Tunable-White-Lampen.
+pub const CCT_CHANNEL_RANGE: (u16, u16) = (2700, 6500);
+
+/// Was ein DMX-Kanal einer Lampe steuert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    Dimmer,
+    Red,
+    Green,
+    Blue,
+    White,
+    Amber,
+    Uv,
+    Cct,
+    Strobe,
+    Cyan,
+    Magenta,
+    Yellow,
+    Hue,
+    Saturation,
+    Value,
+}
+
+/// Ein einzelner DMX-Kanal im Profil. 16-bit Attribute belegen zwei Kanäle:
+/// `Coarse` (MSB) gefolgt von `Fine` (LSB).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Coarse(Attribute),
+    Fine(Attribute),
+    /// Konstanter Wert, z.B. Shutter offen oder Makro aus.
+    Fixed(u8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureProfile {
+    pub name: String,
+    pub channels: Vec<Channel>,
+}
+
+impl FixtureProfile {
+    pub fn new(name: &str, channels: Vec<Channel>) -> Self {
+        Self {
+            name: name.to_string(),
+            channels,
+        }
+    }
+
+    pub fn rgb() -> Self {
+        use Attribute::*;
+        Self::new("RGB", vec![Channel::Coarse(Red), Channel::Coarse(Green), Channel::Coarse(Blue)])
+    }
+
+    pub fn dimmer_rgb() -> Self {
+        use Attribute::*;
+        Self::new(
+            "Dimmer + RGB",
+            vec![Channel::Coarse(Dimmer), Channel::Coarse(Red), Channel::Coarse(Green), Channel::Coarse(Blue)],
+        )
+    }
+
+    pub fn rgbw() -> Self {
+        use Attribute::*;
+        Self::new(
+            "RGBW",
+            vec![Channel::Coarse(Red), Channel::Coarse(Green), Channel::Coarse(Blue), Channel::Coarse(White)],
+        )
+    }
+
+    pub fn rgbwa() -> Self {
+        use Attribute::*;
+        Self::new(
+            "RGBWA",
+            vec![
+                Channel::Coarse(Red), Channel::Coarse(Green), Channel::Coarse(Blue),
+                Channel::Coarse(White), Channel::Coarse(Amber),
+            ],
+        )
+    }
+
+    pub fn rgbwauv() -> Self {
+        use Attribute::*;
+        Self::new(
+            "RGBWA+UV",
+            vec![
+                Channel::Coarse(Red), Channel::Coarse(Green), Channel::Coarse(Blue),
+                Channel::Coarse(White), Channel::Coarse(Amber), Channel::Coarse(Uv),
+            ],
+        )
+    }
+
+    pub fn rgb16() -> Self {
+        use Attribute::*;
+        Self::new(
+            "RGB 16 bit",
+            vec![
+                Channel::Coarse(Red), Channel::Fine(Red),
+                Channel::Coarse(Green), Channel::Fine(Green),
+                Channel::Coarse(Blue), Channel::Fine(Blue),
+            ],
+        )
+    }
+
+    pub fn cmy() -> Self {
+        use Attribute::*;
+        Self::new(
+            "CMY",
+            vec![Channel::Coarse(Cyan), Channel::Coarse(Magenta), Channel::Coarse(Yellow)],
+        )
+    }
+
+    pub fn hsv() -> Self {
+        use Attribute::*;
+        Self::new(
+            "HSV",
+            vec![Channel::Coarse(Hue), Channel::Coarse(Saturation), Channel::Coarse(Value)],
+        )
+    }
+
+    /// Alle eingebauten Profile, z.B. für die Auswahl in der GUI.
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::rgb(),
+            Self::dimmer_rgb(),
+            Self::rgbw(),
+            Self::rgbwa(),
+            Self::rgbwauv(),
+            Self::rgb16(),
+            Self::cmy(),
+            Self::hsv(),
+        ]
+    }
+
+    /// Anzahl der belegten DMX-Kanäle.
+    pub fn footprint(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Ob `attribute` mit 16 bit, also mit einem `Fine`-Kanal, angesteuert wird.
+    pub fn is_16_bit(&self, attribute: Attribute) -> bool {
+        self.channels.contains(&Channel::Fine(attribute))
+    }
+
+    /// Ob das Profil `attribute` überhaupt ansteuert.
+    pub fn has(&self, attribute: Attribute) -> bool {
+        self.channels.contains(&Channel::Coarse(attribute))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub profile: FixtureProfile,
+    /// DMX-Startadresse, 1-basiert wie auf der Lampe eingestellt. Immer 1..=512.
+    address: u16,
+    /// Wie RGB auf Weiß, Amber und UV verteilt wird, falls das Profil solche Kanäle hat.
+    pub extraction: Extraction,
+}
+
+impl Fixture {
+    pub fn new(profile: FixtureProfile, address: u16) -> Self {
+        Self {
+            profile,
+            address: address.clamp(1, DMX_CHANNELS as u16),
+            extraction: Extraction::default(),
+        }
+    }
+
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Werte außerhalb von 1..=512 werden auf den nächsten gültigen Kanal gesetzt.
+    pub fn set_address(&mut self, address: u16) {
+        self.address = address.clamp(1, DMX_CHANNELS as u16);
+    }
+
+    /// Schreibt `color` in die Kanäle dieser Lampe. Kanäle hinter Kanal 512 werden verworfen.
+    ///
+    /// 16-bit Attribute bekommen den vollen `u16` als Coarse/Fine-Paar,
+    /// reine 8-bit Kanäle werden gerundet statt abgeschnitten.
+    pub fn render(&self, color: &Color, universe: &mut [u8; DMX_CHANNELS]) {
+        let emitters = self.extraction.extract(
+            color,
+            self.profile.has(Attribute::White),
+            self.profile.has(Attribute::Amber),
+            self.profile.has(Attribute::Uv),
+        );
+        let value = |attribute| attribute_value(attribute, color, &emitters);
+
+        let start = self.address as usize - 1;
+        for (offset, channel) in self.profile.channels.iter().enumerate() {
+            let Some(slot) = universe.get_mut(start + offset) else { break };
+            *slot = match *channel {
+                Channel::Coarse(attribute) if self.profile.is_16_bit(attribute) => to_dmx16(value(attribute))[0],
+                Channel::Coarse(attribute) => to_dmx8(value(attribute)),
+                Channel::Fine(attribute) => to_dmx16(value(attribute))[1],
+                Channel::Fixed(value) => value,
+            };
+        }
+    }
+}
+
+/// Rendert mehrere Lampen in einen leeren 512-Kanal-Frame.
+pub fn render_universe(fixtures: &[Fixture], color: &Color) -> [u8; DMX_CHANNELS] {
+    let mut universe = [0u8; DMX_CHANNELS];
+    for fixture in fixtures {
+        fixture.render(color, &mut universe);
+    }
+    universe
+}
+
+/// Rundet einen 16-bit Wert auf 0..=255, sodass `u16::MAX` auf 255 und die Mitte auf 127/128 fällt.
+pub fn to_dmx8(value: u16) -> u8 {
+    ((value as u32 * 255 + u16::MAX as u32 / 2) / u16::MAX as u32) as u8
+}
+
+/// Coarse (MSB) und Fine (LSB) eines 16-bit Kanals.
+pub fn to_dmx16(value: u16) -> [u8; 2] {
+    value.to_be_bytes()
+}
+
+fn attribute_value(attribute: Attribute, color: &Color, emitters: &Emitters) -> u16 {
+    let from_unit = |x: f32| (x.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+    match attribute {
+        // Die Helligkeit steckt schon in den Farbkanälen, der Dimmer bleibt offen
+        Attribute::Dimmer => u16::MAX,
+        // RGB ohne die Anteile, die Weiß, Amber und UV übernommen haben
+        Attribute::Red => from_unit(emitters.red),
+        Attribute::Green => from_unit(emitters.green),
+        Attribute::Blue => from_unit(emitters.blue),
+        Attribute::White => from_unit(emitters.white),
+        Attribute::Amber => from_unit(emitters.amber),
+        Attribute::Uv => from_unit(emitters.uv),
+        // Nächste Farbtemperatur der Farbe, warm = 0
+        Attribute::Cct => color.cct().map_or(0, |(kelvin, _)| {
+            let (warm, cold) = CCT_CHANNEL_RANGE;
+            let position = (kelvin.clamp(warm, cold) - warm) as f32 / (cold - warm) as f32;
+            (position * u16::MAX as f32).round() as u16
+        }),
+        // 0 = kein Strobe
+        Attribute::Strobe => 0,
+        Attribute::Cyan => color.cyan(),
+        Attribute::Magenta => color.magenta(),
+        Attribute::Yellow => color.yellow(),
+        Attribute::Hue => color.hue(),
+        Attribute::Saturation => color.saturation(),
+        Attribute::Value => color.value(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orange() -> Color {
+        let mut color = Color::new();
+        color.set_rgb(u16::MAX, 0x8000, 0);
+        color
+    }
+
+    #[test]
+    fn address_stays_in_universe() {
+        let mut fixture = Fixture::new(FixtureProfile::rgb(), 0);
+        assert_eq!(fixture.address(), 1);
+        fixture.set_address(0);
+        assert_eq!(fixture.address(), 1);
+        fixture.set_address(1000);
+        assert_eq!(fixture.address(), 512);
+
+        // Darf nicht mehr überlaufen
+        fixture.set_address(0);
+        let universe = render_universe(&[fixture], &orange());
+        assert_eq!(&universe[..3], &[255, 128, 0]);
+    }
+
+    #[test]
+    fn channels_behind_512_are_dropped() {
+        let universe = render_universe(&[Fixture::new(FixtureProfile::rgb16(), 510)], &orange());
+        assert_eq!(&universe[509..], &[0xFF, 0xFF, 0x80]);
+    }
+
+    #[test]
+    fn dmx_rounding() {
+        assert_eq!(to_dmx8(0), 0);
+        assert_eq!(to_dmx8(u16::MAX), 255);
+        assert_eq!(to_dmx8(0x8000), 128);
+        assert_eq!(to_dmx8(0x7FFF), 127);
+        assert_eq!(to_dmx16(0x1234), [0x12, 0x34]);
+    }
+}
@@ -1,14 +1,51 @@
 use gtk4::prelude::*;
 use gtk4::prelude::{BoxExt, ButtonExt};
-use gtk4::{Application, ApplicationWindow};
+use gtk4::{glib, Application, ApplicationWindow};
 use std::cell::RefCell;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::rc::Rc;
-use crate::color;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use rust_lamp::art_net_sender::{self, ArtNetSender, ArtNode, ArtPollResponder, PortAddress};
+use rust_lamp::color::Color;
+use rust_lamp::extraction::WhiteStrategy;
+use rust_lamp::fixture::{self, Fixture, FixtureProfile};
+use rust_lamp::output::{DmxOutput, Protocol, RecordingOutput};
+use rust_lamp::refresh_loop::{RefreshLoop, RefreshRates};
+use rust_lamp::sacn_sender::{self, SacnSender};
 
-use crate::color_picker::ColorPicker;
+use rust_lamp::color_picker::ColorPicker;
+
+const DEFAULT_TARGET: Ipv4Addr = Ipv4Addr::BROADCAST;
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+const SOURCE_NAME: &str = "rustLamp";
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+/// Erster Eintrag der Node-Auswahl. Erst eine echte Auswahl dahinter ändert das Ziel.
+const NODE_PLACEHOLDER: &str = "– Node wählen –";
+/// Auswahl für die weiße LED: `None` zieht einfach `min(R, G, B)` ab, sonst ist sie auf diese Farbtemperatur kalibriert.
+const WHITE_LEDS: [(&str, Option<u16>); 4] = [
+    ("Weiß = min(RGB)", None),
+    ("Weiß 2700 K", Some(2700)),
+    ("Weiß 4000 K", Some(4000)),
+    ("Weiß 6500 K", Some(6500)),
+];
+
+/// Alles, woraus die Ausgabe gebaut wird. Bei jeder Änderung wird sie neu erzeugt.
+#[derive(Debug, Clone)]
+struct OutputSettings {
+    protocol: Protocol,
+    /// Broadcast bzw. bei sACN Multicast, sonst Unicast an diese Adresse.
+    target: Ipv4Addr,
+    universe: u16,
+    sync: bool,
+    /// Bleibt über Neuaufbauten gleich, damit sACN-Empfänger die Quelle wiedererkennen.
+    cid: [u8; 16],
+}
 
 pub(crate) struct Gui{
-    
+
 }
 
 impl Gui{
@@ -25,28 +62,426 @@ impl Gui{
             let main_box = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
             window1.set_child(Some(&main_box));
 
-            let color = color::Color::new();
+            let color_picker = ColorPicker::new();
+            main_box.append(&color_picker);
+
+            let settings = Rc::new(RefCell::new(OutputSettings {
+                protocol: Protocol::ArtNet,
+                target: DEFAULT_TARGET,
+                universe: 0,
+                sync: false,
+                cid: sacn_sender::random_cid(),
+            }));
+
+            // rustLamp beantwortet ArtPoll, damit es in Node-Listen als Controller auftaucht
+            let responder = match ArtPollResponder::start(ArtNode::controller("rustLamp", "rustLamp Art-Net Controller")) {
+                Ok(responder) => {
+                    responder.set_output_universes(&[PortAddress::from(settings.borrow().universe)]);
+                    Some(responder)
+                }
+                Err(err) => {
+                    eprintln!("Port {} ist belegt, rustLamp antwortet nicht auf ArtPoll: {}", art_net_sender::ART_NET_PORT, err);
+                    None
+                }
+            };
+            let responder = Rc::new(responder);
+
+            // Gesendet wird aus einem eigenen Thread, der den Frame auch ohne Änderung regelmäßig wiederholt
+            let output = match ActiveSender::open(&settings.borrow()) {
+                Ok(sender) => Some(Output {
+                    refresh: RefreshLoop::start(sender.output(), RefreshRates::default()),
+                    sender: RefCell::new(sender),
+                }),
+                Err(err) => {
+                    eprintln!("DMX-Ausgabe konnte nicht geöffnet werden: {}", err);
+                    None
+                }
+            };
+            let output = Rc::new(output);
+
+            let fixture = Rc::new(RefCell::new(Fixture::new(FixtureProfile::rgb(), 1)));
+
+            let live_toggle = gtk4::CheckButton::with_label("Live-Ausgabe");
+            live_toggle.set_active(true);
+
+            // Live-Ausgabe: neuer Frame bei jeder Änderung von Farbe oder Lampe.
+            // Wie oft wirklich gesendet wird, begrenzt der RefreshLoop.
+            let send_live = {
+                let output = output.clone();
+                let settings = settings.clone();
+                let fixture = fixture.clone();
+                let live_toggle = live_toggle.clone();
+                Rc::new(move |color: Color| {
+                    if live_toggle.is_active() {
+                        send_color(&output, settings.borrow().universe, &fixture.borrow(), &color);
+                    }
+                })
+            };
+            {
+                let send_live = send_live.clone();
+                color_picker.connect_color_changed(move |_, color| send_live(color));
+            }
+            {
+                let color_picker = color_picker.clone();
+                let send_live = send_live.clone();
+                live_toggle.connect_toggled(move |_| send_live(color_picker.color()));
+            }
+            send_live(color_picker.color());
+
+            let fixture_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+
+            let profiles = FixtureProfile::builtin();
+            let profile_names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+            let profile_dropdown = gtk4::DropDown::from_strings(&profile_names);
+            profile_dropdown.set_hexpand(true);
+            fixture_box.append(&profile_dropdown);
+
+            let address_spin = gtk4::SpinButton::with_range(1.0, art_net_sender::DMX_CHANNELS as f64, 1.0);
+            address_spin.set_value(1.0);
+            {
+                let fixture = fixture.clone();
+                let color_picker = color_picker.clone();
+                let send_live = send_live.clone();
+                address_spin.connect_value_changed(move |spin| {
+                    fixture.borrow_mut().set_address(spin.value() as u16);
+                    send_live(color_picker.color());
+                });
+            }
+            {
+                let fixture = fixture.clone();
+                let color_picker = color_picker.clone();
+                let send_live = send_live.clone();
+                let address_spin = address_spin.clone();
+                profile_dropdown.connect_selected_notify(move |dropdown| {
+                    let Some(profile) = profiles.get(dropdown.selected() as usize) else { return };
+                    // Startadresse so begrenzen, dass alle Kanäle ins Universum passen
+                    let last_address = art_net_sender::DMX_CHANNELS - profile.footprint() + 1;
+                    address_spin.set_range(1.0, last_address as f64);
+                    fixture.borrow_mut().profile = profile.clone();
+                    send_live(color_picker.color());
+                });
+            }
+            fixture_box.append(&address_spin);
+
+            let white_names: Vec<&str> = WHITE_LEDS.iter().map(|(name, _)| *name).collect();
+            let white_dropdown = gtk4::DropDown::from_strings(&white_names);
+            {
+                let fixture = fixture.clone();
+                let color_picker = color_picker.clone();
+                let send_live = send_live.clone();
+                white_dropdown.connect_selected_notify(move |dropdown| {
+                    let Some((_, kelvin)) = WHITE_LEDS.get(dropdown.selected() as usize) else { return };
+                    fixture.borrow_mut().extraction.white = match kelvin {
+                        Some(kelvin) => WhiteStrategy::calibrated_kelvin(*kelvin),
+                        None => WhiteStrategy::MinSubtraction,
+                    };
+                    send_live(color_picker.color());
+                });
+            }
+            fixture_box.append(&white_dropdown);
+            main_box.append(&fixture_box);
 
-            let color = Rc::new(RefCell::new(color));
-            let color_picker = ColorPicker::new(color.clone());
-            main_box.append(color_picker.widget());
+            let output_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+
+            // Mit Platzhalter, damit die DropDown nicht von selbst den ersten gefundenen Node auswählt
+            let nodes: Rc<RefCell<Vec<ArtNode>>> = Rc::new(RefCell::new(Vec::new()));
+            let node_names = gtk4::StringList::new(&[NODE_PLACEHOLDER]);
+            let node_dropdown = gtk4::DropDown::new(Some(node_names.clone()), None::<gtk4::Expression>);
+            node_dropdown.set_hexpand(true);
+
+            let target_entry = gtk4::Entry::new();
+            target_entry.set_text(&DEFAULT_TARGET.to_string());
+            target_entry.set_hexpand(true);
+            {
+                let settings = settings.clone();
+                let output = output.clone();
+                let responder = responder.clone();
+                let node_dropdown = node_dropdown.clone();
+                target_entry.connect_activate(move |entry| {
+                    match entry.text().parse::<Ipv4Addr>() {
+                        Ok(ip) => {
+                            settings.borrow_mut().target = ip;
+                            apply_settings(&settings.borrow(), &output, &responder);
+                            // Zurück auf den Platzhalter, damit derselbe Node wieder gewählt werden kann
+                            node_dropdown.set_selected(0);
+                        }
+                        Err(_) => eprintln!("Ungültige IP-Adresse: {}", entry.text()),
+                    }
+                });
+            }
+            output_box.append(&target_entry);
+
+            let protocol_names: Vec<&str> = Protocol::ALL.iter().map(|p| p.name()).collect();
+            let protocol_dropdown = gtk4::DropDown::from_strings(&protocol_names);
+            output_box.append(&protocol_dropdown);
+
+            let universe_spin = gtk4::SpinButton::with_range(0.0, 0x7FFF as f64, 1.0);
+            universe_spin.set_value(settings.borrow().universe as f64);
+            {
+                let settings = settings.clone();
+                let output = output.clone();
+                let responder = responder.clone();
+                universe_spin.connect_value_changed(move |spin| {
+                    settings.borrow_mut().universe = spin.value() as u16;
+                    apply_settings(&settings.borrow(), &output, &responder);
+                });
+            }
+            output_box.append(&universe_spin);
+            {
+                let settings = settings.clone();
+                let output = output.clone();
+                let responder = responder.clone();
+                let universe_spin = universe_spin.clone();
+                protocol_dropdown.connect_selected_notify(move |dropdown| {
+                    let Some(protocol) = Protocol::ALL.get(dropdown.selected() as usize) else { return };
+                    settings.borrow_mut().protocol = *protocol;
+                    // Art-Net zählt Port-Adressen ab 0, sACN Universen ab 1
+                    match protocol {
+                        Protocol::ArtNet => universe_spin.set_range(0.0, 0x7FFF as f64),
+                        Protocol::Sacn => universe_spin.set_range(1.0, 63999.0),
+                        Protocol::Recording => universe_spin.set_range(0.0, u16::MAX as f64),
+                    }
+                    apply_settings(&settings.borrow(), &output, &responder);
+                });
+            }
+
+            let node_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+            {
+                let nodes = nodes.clone();
+                let settings = settings.clone();
+                let output = output.clone();
+                let responder = responder.clone();
+                let target_entry = target_entry.clone();
+                let universe_spin = universe_spin.clone();
+                node_dropdown.connect_selected_notify(move |dropdown| {
+                    // Index 0 ist der Platzhalter
+                    let Some(index) = (dropdown.selected() as usize).checked_sub(1) else { return };
+                    let nodes = nodes.borrow();
+                    let Some(node) = nodes.get(index) else { return };
+                    settings.borrow_mut().target = node.ip;
+                    target_entry.set_text(&node.ip.to_string());
+                    apply_settings(&settings.borrow(), &output, &responder);
+                    if let Some(universe) = node.output_universes().first() {
+                        // löst über connect_value_changed ebenfalls apply_settings aus
+                        universe_spin.set_value(universe.to_u16() as f64);
+                    }
+                });
+            }
+            node_box.append(&node_dropdown);
+
+            let discover_button = gtk4::Button::with_label("Nodes suchen");
+            {
+                let responder = responder.clone();
+                let nodes = nodes.clone();
+                let node_dropdown = node_dropdown.clone();
+                discover_button.connect_clicked(move |button| {
+                    button.set_sensitive(false);
+                    let broadcast = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::BROADCAST, art_net_sender::ART_NET_PORT));
+
+                    // Läuft der Responder, hält er Port 6454 und sammelt die Antworten selbst
+                    if let Some(running) = responder.as_ref() {
+                        if let Err(err) = running.poll(broadcast) {
+                            eprintln!("Art-Net Node-Suche fehlgeschlagen: {}", err);
+                        }
+                        let button = button.clone();
+                        let responder = responder.clone();
+                        let nodes = nodes.clone();
+                        let node_dropdown = node_dropdown.clone();
+                        glib::timeout_add_local_once(DISCOVERY_TIMEOUT, move || {
+                            if let Some(responder) = responder.as_ref() {
+                                show_nodes(responder.nodes(), &nodes, &node_dropdown);
+                            }
+                            button.set_sensitive(true);
+                        });
+                        return;
+                    }
+
+                    // Die Suche wartet auf Antworten, also nicht im GTK-Thread
+                    let (tx, rx) = mpsc::channel();
+                    thread::spawn(move || {
+                        let _ = tx.send(art_net_sender::discover_nodes(broadcast, DISCOVERY_TIMEOUT));
+                    });
+
+                    let button = button.clone();
+                    let nodes = nodes.clone();
+                    let node_dropdown = node_dropdown.clone();
+                    glib::timeout_add_local(Duration::from_millis(100), move || {
+                        let result = match rx.try_recv() {
+                            Ok(result) => result,
+                            Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                            Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+                        };
+                        match result {
+                            Ok(found) => show_nodes(found, &nodes, &node_dropdown),
+                            Err(err) => eprintln!("Art-Net Node-Suche fehlgeschlagen: {}", err),
+                        }
+                        button.set_sensitive(true);
+                        glib::ControlFlow::Break
+                    });
+                });
+            }
+            node_box.append(&discover_button);
+            main_box.append(&node_box);
+
+            let sync_toggle = gtk4::CheckButton::with_label("Sync");
+            {
+                let settings = settings.clone();
+                let output = output.clone();
+                let responder = responder.clone();
+                sync_toggle.connect_toggled(move |toggle| {
+                    settings.borrow_mut().sync = toggle.is_active();
+                    apply_settings(&settings.borrow(), &output, &responder);
+                });
+            }
+            output_box.append(&sync_toggle);
+
+            output_box.append(&live_toggle);
+
+            let status_label = gtk4::Label::new(None);
+            status_label.set_xalign(0.0);
+            {
+                let output = output.clone();
+                let status_label = status_label.clone();
+                let update_status = move || {
+                    match output.as_ref() {
+                        Some(output) => status_label.set_text(&output.refresh.status().to_string()),
+                        None => status_label.set_text("Keine DMX-Ausgabe"),
+                    }
+                    glib::ControlFlow::Continue
+                };
+                update_status();
+                glib::timeout_add_local(STATUS_INTERVAL, update_status);
+            }
 
-            let color_clone = color.clone();
             let button = gtk4::Button::with_label("Farbe ausgeben");
             button.connect_clicked(move |_| {
-                let c = color_clone.borrow();
-                println!(
-                    "Chosen color: - R: {}, G: {}, B: {}",
-                    c.red, c.green, c.blue
-                );
+                send_color(&output, settings.borrow().universe, &fixture.borrow(), &color_picker.color());
             });
 
-            main_box.append(&button);
+            output_box.append(&button);
+            main_box.append(&output_box);
+
+            main_box.append(&status_label);
             window1.show();
         });
         app.run();
-        
-        let gui = Gui{};
-        gui
+
+        Gui{}
     }
-}
\ No newline at end of file
+}
+
+/// Der [`RefreshLoop`] zusammen mit dem Sender, der gerade darin läuft.
+struct Output {
+    refresh: RefreshLoop,
+    sender: RefCell<ActiveSender>,
+}
+
+/// Griff auf den laufenden Sender. Geänderte Einstellungen konfigurieren ihn um, statt ihn neu
+/// anzulegen, sonst fingen die Sequenznummern wieder von vorne an und Empfänger verwürfen Pakete.
+enum ActiveSender {
+    ArtNet(Arc<Mutex<ArtNetSender>>),
+    Sacn(Arc<Mutex<SacnSender>>),
+    Recording(RecordingOutput),
+}
+
+impl ActiveSender {
+    fn open(settings: &OutputSettings) -> io::Result<Self> {
+        let sender = match settings.protocol {
+            Protocol::ArtNet => ActiveSender::ArtNet(Arc::new(Mutex::new(ArtNetSender::new(art_net_target(settings))?))),
+            Protocol::Sacn => ActiveSender::Sacn(Arc::new(Mutex::new(SacnSender::new(SOURCE_NAME)?))),
+            Protocol::Recording => ActiveSender::Recording(RecordingOutput::new()),
+        };
+        sender.configure(settings);
+        Ok(sender)
+    }
+
+    fn protocol(&self) -> Protocol {
+        match self {
+            ActiveSender::ArtNet(_) => Protocol::ArtNet,
+            ActiveSender::Sacn(_) => Protocol::Sacn,
+            ActiveSender::Recording(_) => Protocol::Recording,
+        }
+    }
+
+    fn configure(&self, settings: &OutputSettings) {
+        match self {
+            ActiveSender::ArtNet(sender) => {
+                let mut sender = sender.lock().unwrap();
+                sender.set_target(art_net_target(settings));
+                sender.set_sync(settings.sync);
+            }
+            ActiveSender::Sacn(sender) => {
+                let mut sender = sender.lock().unwrap();
+                sender.set_cid(settings.cid);
+                // sACN hat keinen Broadcast, dafür gibt es die Multicast-Gruppen
+                let multicast = settings.target.is_broadcast() || settings.target.is_unspecified();
+                sender.set_unicast(if multicast { None } else { Some(settings.target) });
+                sender.set_sync_universe(settings.sync.then_some(settings.universe));
+            }
+            ActiveSender::Recording(_) => {}
+        }
+    }
+
+    /// Ausgabe für den [`RefreshLoop`], sie teilt sich den Zustand mit diesem Griff.
+    fn output(&self) -> Box<dyn DmxOutput> {
+        match self {
+            ActiveSender::ArtNet(sender) => Box::new(sender.clone()),
+            ActiveSender::Sacn(sender) => Box::new(sender.clone()),
+            ActiveSender::Recording(recording) => Box::new(recording.clone()),
+        }
+    }
+}
+
+fn art_net_target(settings: &OutputSettings) -> SocketAddr {
+    SocketAddr::V4(SocketAddrV4::new(settings.target, art_net_sender::ART_NET_PORT))
+}
+
+fn apply_settings(settings: &OutputSettings, output: &Option<Output>, responder: &Option<ArtPollResponder>) {
+    if let Some(responder) = responder {
+        let universes = match settings.protocol {
+            Protocol::ArtNet => vec![PortAddress::from(settings.universe)],
+            Protocol::Sacn | Protocol::Recording => Vec::new(),
+        };
+        responder.set_output_universes(&universes);
+    }
+
+    let Some(output) = output else { return };
+    let mut sender = output.sender.borrow_mut();
+    if sender.protocol() == settings.protocol {
+        sender.configure(settings);
+        output.refresh.resend();
+    } else {
+        match ActiveSender::open(settings) {
+            Ok(new_sender) => {
+                output.refresh.set_output(new_sender.output());
+                *sender = new_sender;
+            }
+            Err(err) => eprintln!("DMX-Ausgabe konnte nicht geöffnet werden: {}", err),
+        }
+    }
+
+    // Die eine Lampe zieht mit ins neue Universum um
+    let frames = output.refresh.frames().into_iter().map(|(_, frame)| (settings.universe, frame)).collect();
+    output.refresh.set_frames(frames);
+}
+
+/// Ersetzt die Einträge hinter dem Platzhalter. Die Auswahl geht vorher auf den Platzhalter,
+/// so löst das Neubefüllen keinen Wechsel des Ziels aus.
+fn show_nodes(found: Vec<ArtNode>, nodes: &RefCell<Vec<ArtNode>>, node_dropdown: &gtk4::DropDown) {
+    let Some(node_names) = node_dropdown.model().and_downcast::<gtk4::StringList>() else { return };
+    let names: Vec<String> = found
+        .iter()
+        .map(|node| format!("{} ({})", node.short_name, node.ip))
+        .collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    node_dropdown.set_selected(0);
+    *nodes.borrow_mut() = found;
+    node_names.splice(1, node_names.n_items() - 1, &names);
+}
+
+fn send_color(output: &Option<Output>, universe: u16, fixture: &Fixture, color: &Color) {
+    if let Some(output) = output {
+        output.refresh.set_frames(vec![(universe, fixture::render_universe(std::slice::from_ref(fixture), color))]);
+    }
+}
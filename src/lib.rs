@@ -0,0 +1,11 @@
+pub mod color_picker;
+pub mod art_net_sender;
+pub mod cct;
+pub mod color;
+pub mod color_space;
+pub mod extraction;
+pub mod fixture;
+pub mod output;
+pub mod refresh_loop;
+pub mod sacn_sender;
+pub mod wheel_raster;
@@ -1,6 +1,3 @@
-mod color_picker;
-mod art_net_sender;
-mod color;
 mod gui;
 
 fn main() {
@@ -0,0 +1,274 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex};
+use crate::art_net_sender::{ArtNetSender, PortAddress, DMX_CHANNELS};
+
+/// Ein Weg, über den DMX-Universen ausgegeben werden: Art-Net, sACN oder im Speicher für Tests.
+///
+/// Die Universumsnummer ist protokollabhängig: bei Art-Net die 15-bit Port-Address,
+/// bei sACN das E1.31-Universum 1..=63999.
+pub trait DmxOutput: Send {
+    fn send_universe(&mut self, universe: u16, data: &[u8; DMX_CHANNELS]) -> io::Result<()>;
+
+    /// Schließt einen Batch von Universen ab, z.B. mit einem Sync-Paket.
+    fn flush(&mut self) -> io::Result<()>;
+
+    fn status(&self) -> OutputStatus;
+}
+
+/// Momentaufnahme einer Ausgabe, z.B. für eine Statuszeile in der GUI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputStatus {
+    pub protocol: &'static str,
+    pub destination: String,
+    pub packets_sent: u64,
+    /// Fehler des letzten Sendeversuchs, `None` wenn er geklappt hat.
+    pub last_error: Option<String>,
+}
+
+impl fmt::Display for OutputStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} → {}, {} Pakete", self.protocol, self.destination, self.packets_sent)?;
+        if let Some(err) = &self.last_error {
+            write!(f, ", Fehler: {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    ArtNet,
+    Sacn,
+    /// Nichts senden, nur mitschreiben. Zum Ausprobieren ohne Lampe.
+    Recording,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 3] = [Protocol::ArtNet, Protocol::Sacn, Protocol::Recording];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Protocol::ArtNet => "Art-Net",
+            Protocol::Sacn => "sACN (E1.31)",
+            Protocol::Recording => "Nur aufzeichnen",
+        }
+    }
+}
+
+/// Geteilter Sender: Ein [`RefreshLoop`](crate::refresh_loop::RefreshLoop) sendet damit, während der
+/// Besitzer ihn umkonfiguriert. Sequenznummern laufen so über Einstellungsänderungen hinweg weiter.
+impl<T: DmxOutput> DmxOutput for Arc<Mutex<T>> {
+    fn send_universe(&mut self, universe: u16, data: &[u8; DMX_CHANNELS]) -> io::Result<()> {
+        self.lock().unwrap().send_universe(universe, data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock().unwrap().flush()
+    }
+
+    fn status(&self) -> OutputStatus {
+        self.lock().unwrap().status()
+    }
+}
+
+impl DmxOutput for ArtNetSender {
+    fn send_universe(&mut self, universe: u16, data: &[u8; DMX_CHANNELS]) -> io::Result<()> {
+        self.send_dmx_to(PortAddress::from(universe), data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.sync_enabled() {
+            self.send_sync()?;
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> OutputStatus {
+        OutputStatus {
+            protocol: "Art-Net",
+            destination: self.target().to_string(),
+            packets_sent: self.packets_sent(),
+            last_error: self.last_error().map(str::to_string),
+        }
+    }
+}
+
+/// Ein gesendetes Universum, wie es [`RecordingOutput`] mitschreibt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedFrame {
+    pub universe: u16,
+    pub data: [u8; DMX_CHANNELS],
+}
+
+/// Damit eine lange laufende Aufzeichnung nicht unbegrenzt wächst.
+const MAX_RECORDED_FRAMES: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct Recording {
+    frames: VecDeque<RecordedFrame>,
+    flushes: usize,
+    frames_sent: u64,
+}
+
+/// Schreibt alle Frames im Speicher mit, statt sie zu senden.
+///
+/// Klone teilen sich die Aufzeichnung: ein Klon kann an den `RefreshLoop` übergeben werden,
+/// über den anderen lässt sich danach prüfen, welche Kanalwerte herausgekommen sind.
+/// Es werden höchstens die letzten 10 000 Frames behalten.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingOutput {
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl RecordingOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn frames(&self) -> Vec<RecordedFrame> {
+        self.recording.lock().unwrap().frames.iter().cloned().collect()
+    }
+
+    /// Der zuletzt gesendete Frame für `universe`.
+    pub fn last_frame(&self, universe: u16) -> Option<[u8; DMX_CHANNELS]> {
+        self.recording
+            .lock()
+            .unwrap()
+            .frames
+            .iter()
+            .rev()
+            .find(|frame| frame.universe == universe)
+            .map(|frame| frame.data)
+    }
+
+    /// Wert eines Kanals im zuletzt gesendeten Frame, `channel` 1-basiert wie die DMX-Adresse.
+    pub fn channel(&self, universe: u16, channel: u16) -> Option<u8> {
+        let index = (channel as usize).checked_sub(1)?;
+        self.last_frame(universe)?.get(index).copied()
+    }
+
+    pub fn flush_count(&self) -> usize {
+        self.recording.lock().unwrap().flushes
+    }
+
+    pub fn clear(&self) {
+        let mut recording = self.recording.lock().unwrap();
+        recording.frames.clear();
+        recording.flushes = 0;
+        recording.frames_sent = 0;
+    }
+}
+
+impl DmxOutput for RecordingOutput {
+    fn send_universe(&mut self, universe: u16, data: &[u8; DMX_CHANNELS]) -> io::Result<()> {
+        let mut recording = self.recording.lock().unwrap();
+        if recording.frames.len() == MAX_RECORDED_FRAMES {
+            recording.frames.pop_front();
+        }
+        recording.frames.push_back(RecordedFrame { universe, data: *data });
+        recording.frames_sent += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.recording.lock().unwrap().flushes += 1;
+        Ok(())
+    }
+
+    fn status(&self) -> OutputStatus {
+        OutputStatus {
+            protocol: "Speicher",
+            destination: "Aufzeichnung".to_string(),
+            packets_sent: self.recording.lock().unwrap().frames_sent,
+            last_error: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(first: u8) -> [u8; DMX_CHANNELS] {
+        let mut data = [0; DMX_CHANNELS];
+        data[0] = first;
+        data[DMX_CHANNELS - 1] = 99;
+        data
+    }
+
+    #[test]
+    fn shared_sender_keeps_sequence_when_retargeted() {
+        use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+
+        let first = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let second = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let sender = Arc::new(Mutex::new(ArtNetSender::new(first.local_addr().unwrap()).unwrap()));
+        let mut output: Box<dyn DmxOutput> = Box::new(sender.clone());
+
+        let mut buffer = [0u8; 1024];
+        output.send_universe(1, &frame(1)).unwrap();
+        first.recv_from(&mut buffer).unwrap();
+        assert_eq!(buffer[12], 1);
+
+        sender.lock().unwrap().set_target(second.local_addr().unwrap());
+        output.send_universe(1, &frame(2)).unwrap();
+        second.recv_from(&mut buffer).unwrap();
+        assert_eq!(buffer[12], 2);
+        assert_eq!(output.status().destination, second.local_addr().unwrap().to_string());
+    }
+
+    #[test]
+    fn clones_share_the_recording() {
+        let recording = RecordingOutput::new();
+        let mut output: Box<dyn DmxOutput> = Box::new(recording.clone());
+
+        output.send_universe(1, &frame(10)).unwrap();
+        output.send_universe(2, &frame(20)).unwrap();
+        output.send_universe(1, &frame(30)).unwrap();
+        output.flush().unwrap();
+
+        assert_eq!(recording.frames().len(), 3);
+        assert_eq!(recording.last_frame(1), Some(frame(30)));
+        assert_eq!(recording.last_frame(3), None);
+        assert_eq!(recording.flush_count(), 1);
+        assert_eq!(output.status().packets_sent, 3);
+    }
+
+    #[test]
+    fn channels_are_one_based() {
+        let recording = RecordingOutput::new();
+        recording.clone().send_universe(1, &frame(42)).unwrap();
+
+        assert_eq!(recording.channel(1, 1), Some(42));
+        assert_eq!(recording.channel(1, 512), Some(99));
+        assert_eq!(recording.channel(1, 0), None);
+        assert_eq!(recording.channel(1, 513), None);
+    }
+
+    #[test]
+    fn clear_resets_everything() {
+        let recording = RecordingOutput::new();
+        let mut output = recording.clone();
+        output.send_universe(1, &frame(1)).unwrap();
+        output.flush().unwrap();
+
+        recording.clear();
+        assert!(recording.frames().is_empty());
+        assert_eq!(recording.flush_count(), 0);
+        assert_eq!(output.status().packets_sent, 0);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_frames() {
+        let mut recording = RecordingOutput::new();
+        for i in 0..MAX_RECORDED_FRAMES + 5 {
+            recording.send_universe(1, &frame(i as u8)).unwrap();
+        }
+        let frames = recording.frames();
+        assert_eq!(frames.len(), MAX_RECORDED_FRAMES);
+        assert_eq!(frames[0].data[0], 5);
+        assert_eq!(recording.status().packets_sent, MAX_RECORDED_FRAMES as u64 + 5);
+    }
+}
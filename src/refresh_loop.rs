@@ -0,0 +1,281 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::art_net_sender::DMX_CHANNELS;
+use crate::output::{DmxOutput, OutputStatus};
+
+/// Wie oft der aktuelle Frame gesendet wird.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefreshRates {
+    /// Keep-Alive, solange sich nichts ändert. Viele Nodes gehen sonst nach ein paar Sekunden auf Blackout.
+    pub idle_hz: f32,
+    /// Obergrenze, während sich der Frame ändert. 44 Hz ist die volle DMX-Rate bei 512 Kanälen.
+    pub active_hz: f32,
+}
+
+impl Default for RefreshRates {
+    fn default() -> Self {
+        Self {
+            idle_hz: 4.0,
+            active_hz: 44.0,
+        }
+    }
+}
+
+impl RefreshRates {
+    fn idle_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.idle_hz.max(0.1))
+    }
+
+    fn active_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.active_hz.max(0.1))
+    }
+}
+
+/// Ein Universum mit seinem aktuellen Frame.
+pub type UniverseFrame = (u16, [u8; DMX_CHANNELS]);
+
+struct State {
+    /// Alle Universen eines Batches, z.B. Schreibtischlampe und Regal-Streifen.
+    frames: Vec<UniverseFrame>,
+    changed: bool,
+    running: bool,
+    rates: RefreshRates,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+    output: Mutex<Box<dyn DmxOutput>>,
+}
+
+/// Sendet die aktuellen Frames aus einem eigenen Thread, damit der GTK-Mainloop nie blockiert.
+/// Änderungen gehen mit bis zu `active_hz` raus, sonst wird mit `idle_hz` wiederholt.
+///
+/// Jeder Durchlauf sendet alle Universen und ruft danach einmal [`DmxOutput::flush`] auf,
+/// mit eingeschaltetem Sync übernehmen die Empfänger sie also gleichzeitig.
+pub struct RefreshLoop {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RefreshLoop {
+    /// Sendet erst, sobald mit [`Self::set_frame`] oder [`Self::set_frames`] ein Universum dazukommt.
+    pub fn start(output: Box<dyn DmxOutput>, rates: RefreshRates) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                frames: Vec::new(),
+                changed: false,
+                running: true,
+                rates,
+            }),
+            wake: Condvar::new(),
+            output: Mutex::new(output),
+        });
+
+        let handle = {
+            let shared = shared.clone();
+            thread::spawn(move || run(&shared))
+        };
+
+        Self {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    /// Übernimmt einen neuen Frame für `universe`, die anderen Universen bleiben.
+    /// Er wird gesendet, sobald das Ratenlimit es erlaubt.
+    pub fn set_frame(&self, universe: u16, frame: [u8; DMX_CHANNELS]) {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.frames.iter_mut().find(|(u, _)| *u == universe) {
+            Some((_, current)) if *current == frame => return,
+            Some((_, current)) => *current = frame,
+            None => state.frames.push((universe, frame)),
+        }
+        state.changed = true;
+        self.shared.wake.notify_one();
+    }
+
+    /// Ersetzt alle Universen, z.B. wenn sich die Zuordnung der Lampen ändert.
+    pub fn set_frames(&self, frames: Vec<UniverseFrame>) {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.frames != frames {
+            state.frames = frames;
+            state.changed = true;
+            self.shared.wake.notify_one();
+        }
+    }
+
+    pub fn frames(&self) -> Vec<UniverseFrame> {
+        self.shared.state.lock().unwrap().frames.clone()
+    }
+
+    pub fn rates(&self) -> RefreshRates {
+        self.shared.state.lock().unwrap().rates
+    }
+
+    pub fn set_rates(&self, rates: RefreshRates) {
+        self.shared.state.lock().unwrap().rates = rates;
+        self.shared.wake.notify_one();
+    }
+
+    /// Tauscht das Ausgabeprotokoll aus, z.B. nach geänderten Einstellungen. Die Frames bleiben erhalten.
+    pub fn set_output(&self, output: Box<dyn DmxOutput>) {
+        *self.shared.output.lock().unwrap() = output;
+        self.mark_changed();
+    }
+
+    /// Sendet die Frames beim nächsten Durchlauf erneut, z.B. nachdem der Sender umkonfiguriert wurde.
+    pub fn resend(&self) {
+        self.mark_changed();
+    }
+
+    pub fn status(&self) -> OutputStatus {
+        self.shared.output.lock().unwrap().status()
+    }
+
+    fn mark_changed(&self) {
+        self.shared.state.lock().unwrap().changed = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+impl Drop for RefreshLoop {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().running = false;
+        self.shared.wake.notify_one();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn run(shared: &Shared) {
+    let mut last_send: Option<Instant> = None;
+    let mut state = shared.state.lock().unwrap();
+
+    while state.running {
+        let interval = if state.changed {
+            state.rates.active_interval()
+        } else {
+            state.rates.idle_interval()
+        };
+        let due = last_send.map_or(Duration::ZERO, |last| interval.saturating_sub(last.elapsed()));
+
+        if !due.is_zero() {
+            state = shared.wake.wait_timeout(state, due).unwrap().0;
+            continue;
+        }
+
+        let frames = state.frames.clone();
+        state.changed = false;
+        drop(state);
+
+        if !frames.is_empty() {
+            let mut output = shared.output.lock().unwrap();
+            // Ein fehlgeschlagenes Universum hält die anderen nicht auf
+            let mut result = Ok(());
+            for (universe, frame) in &frames {
+                result = result.and(output.send_universe(*universe, frame));
+            }
+            if let Err(err) = result.and_then(|_| output.flush()) {
+                eprintln!("DMX Senden fehlgeschlagen: {}", err);
+            }
+        }
+        last_send = Some(Instant::now());
+
+        state = shared.state.lock().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::fixture::{self, Fixture, FixtureProfile};
+    use crate::output::RecordingOutput;
+
+    const FAST: RefreshRates = RefreshRates { idle_hz: 50.0, active_hz: 1000.0 };
+
+    /// Wartet, bis der Thread `expected` für `universe` gesendet hat.
+    fn wait_for_frame(recording: &RecordingOutput, universe: u16, expected: &[u8; DMX_CHANNELS]) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while recording.last_frame(universe).as_ref() != Some(expected) {
+            assert!(Instant::now() < deadline, "Frame wurde nicht gesendet");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn sends_rendered_fixtures() {
+        let recording = RecordingOutput::new();
+        let refresh = RefreshLoop::start(Box::new(recording.clone()), FAST);
+
+        let mut color = Color::new();
+        color.set_rgb(u16::MAX, 0x8000, 0);
+        let fixtures = [
+            Fixture::new(FixtureProfile::rgb(), 1),
+            Fixture::new(FixtureProfile::rgb16(), 10),
+            Fixture::new(FixtureProfile::dimmer_rgb(), 510),
+        ];
+        let frame = fixture::render_universe(&fixtures, &color);
+        refresh.set_frame(3, frame);
+        wait_for_frame(&recording, 3, &frame);
+
+        // RGB auf 8 bit gerundet
+        assert_eq!(recording.channel(3, 1), Some(255));
+        assert_eq!(recording.channel(3, 2), Some(128));
+        assert_eq!(recording.channel(3, 3), Some(0));
+        // 16 bit als Coarse/Fine
+        assert_eq!(recording.channel(3, 10), Some(0xFF));
+        assert_eq!(recording.channel(3, 11), Some(0xFF));
+        assert_eq!(recording.channel(3, 12), Some(0x80));
+        assert_eq!(recording.channel(3, 13), Some(0x00));
+        // Dimmer offen, Blau würde hinter Kanal 512 liegen
+        assert_eq!(recording.channel(3, 510), Some(255));
+        assert_eq!(recording.channel(3, 512), Some(128));
+        assert_eq!(recording.channel(3, 4), Some(0));
+    }
+
+    #[test]
+    fn follows_universe_changes_and_keeps_alive() {
+        let recording = RecordingOutput::new();
+        let refresh = RefreshLoop::start(Box::new(recording.clone()), FAST);
+
+        let mut frame = [0; DMX_CHANNELS];
+        frame[0] = 7;
+        refresh.set_frame(1, frame);
+        wait_for_frame(&recording, 1, &frame);
+
+        refresh.set_frames(vec![(2, frame)]);
+        wait_for_frame(&recording, 2, &frame);
+        assert_eq!(refresh.frames(), vec![(2, frame)]);
+
+        // Ohne Änderung wird weiter mit idle_hz wiederholt, jeweils mit Flush
+        let flushes = recording.flush_count();
+        thread::sleep(Duration::from_millis(100));
+        assert!(recording.flush_count() > flushes);
+        assert_eq!(refresh.rates(), FAST);
+    }
+
+    #[test]
+    fn sends_all_universes_before_one_flush() {
+        let recording = RecordingOutput::new();
+        // Langsames Wiederholen, damit nur der eine Batch gezählt wird
+        let slow = RefreshRates { idle_hz: 0.5, active_hz: 1000.0 };
+        let refresh = RefreshLoop::start(Box::new(recording.clone()), slow);
+
+        let mut desk = [0; DMX_CHANNELS];
+        desk[0] = 1;
+        let mut shelf = [0; DMX_CHANNELS];
+        shelf[0] = 2;
+        refresh.set_frames(vec![(1, desk), (2, shelf)]);
+        wait_for_frame(&recording, 2, &shelf);
+
+        let universes: Vec<u16> = recording.frames().iter().map(|f| f.universe).collect();
+        assert_eq!(universes, [1, 2]);
+        assert_eq!(recording.last_frame(1), Some(desk));
+        assert_eq!(recording.flush_count(), 1);
+    }
+}
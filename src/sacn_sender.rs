@@ -0,0 +1,391 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::SystemTime;
+use crate::art_net_sender::DMX_CHANNELS;
+use crate::output::{DmxOutput, OutputStatus};
+
+pub const SACN_PORT: u16 = 5568;
+pub const ACN_PACKET_IDENTIFIER: &[u8; 12] = b"ASC-E1.17\0\0\0";
+pub const DEFAULT_PRIORITY: u8 = 100;
+pub const MAX_PRIORITY: u8 = 200;
+
+pub const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
+pub const VECTOR_ROOT_E131_EXTENDED: u32 = 0x0000_0008;
+pub const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
+pub const VECTOR_E131_EXTENDED_SYNCHRONIZATION: u32 = 0x0000_0001;
+pub const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+
+const DATA_PACKET_LENGTH: usize = 126 + DMX_CHANNELS;
+const SYNC_PACKET_LENGTH: usize = 49;
+const SOURCE_NAME_LENGTH: usize = 64;
+
+/// Multicast-Gruppe eines Universums: 239.255.{hi}.{lo}.
+pub fn multicast_address(universe: u16) -> Ipv4Addr {
+    let [hi, lo] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, hi, lo)
+}
+
+/// Sendet DMX über sACN (ANSI E1.31), per Multicast oder Unicast.
+pub struct SacnSender {
+    socket: UdpSocket,
+    cid: [u8; 16],
+    source_name: String,
+    priority: u8,
+    unicast: Option<Ipv4Addr>,
+    port: u16,
+    sync_universe: Option<u16>,
+    sequences: HashMap<u16, u8>,
+    sync_sequence: u8,
+    packets_sent: u64,
+    last_error: Option<String>,
+}
+
+impl SacnSender {
+    pub fn new(source_name: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_multicast_ttl_v4(16)?;
+        socket.set_multicast_loop_v4(true)?;
+
+        Ok(Self {
+            socket,
+            cid: random_cid(),
+            source_name: source_name.to_string(),
+            priority: DEFAULT_PRIORITY,
+            unicast: None,
+            port: SACN_PORT,
+            sync_universe: None,
+            sequences: HashMap::new(),
+            sync_sequence: 0,
+            packets_sent: 0,
+            last_error: None,
+        })
+    }
+
+    pub fn cid(&self) -> [u8; 16] {
+        self.cid
+    }
+
+    /// Empfänger unterscheiden Quellen an der CID, sie sollte pro Installation fest bleiben.
+    pub fn set_cid(&mut self, cid: [u8; 16]) {
+        self.cid = cid;
+    }
+
+    pub fn set_source_name(&mut self, source_name: &str) {
+        self.source_name = source_name.to_string();
+    }
+
+    pub fn set_priority(&mut self, priority: u8) {
+        self.priority = priority.min(MAX_PRIORITY);
+    }
+
+    /// `None` sendet per Multicast an die Gruppe des Universums, sonst direkt an diese Adresse.
+    pub fn set_unicast(&mut self, unicast: Option<Ipv4Addr>) {
+        self.unicast = unicast;
+    }
+
+    /// Zielport für Unicast und Multicast, normalerweise [`SACN_PORT`].
+    pub fn set_port(&mut self, port: u16) {
+        self.port = port;
+    }
+
+    /// Mit einem Sync-Universum halten Empfänger die Daten, bis [`DmxOutput::flush`] das Sync-Paket sendet.
+    pub fn set_sync_universe(&mut self, sync_universe: Option<u16>) {
+        self.sync_universe = sync_universe;
+    }
+
+    fn destination(&self, universe: u16) -> SocketAddr {
+        let ip = self.unicast.unwrap_or_else(|| multicast_address(universe));
+        SocketAddr::V4(SocketAddrV4::new(ip, self.port))
+    }
+
+    fn send_packet(&mut self, packet: &[u8], universe: u16) -> io::Result<()> {
+        match self.socket.send_to(packet, self.destination(universe)) {
+            Ok(_) => {
+                self.packets_sent += 1;
+                self.last_error = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.last_error = Some(err.to_string());
+                Err(err)
+            }
+        }
+    }
+}
+
+impl DmxOutput for SacnSender {
+    fn send_universe(&mut self, universe: u16, data: &[u8; DMX_CHANNELS]) -> io::Result<()> {
+        let sequence = self.sequences.entry(universe).or_insert(0);
+        *sequence = sequence.wrapping_add(1);
+
+        let packet = build_data_packet(&DataPacket {
+            cid: self.cid,
+            source_name: &self.source_name,
+            priority: self.priority,
+            sync_universe: self.sync_universe.unwrap_or(0),
+            sequence: *sequence,
+            universe,
+            data,
+        });
+        self.send_packet(&packet, universe)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(sync_universe) = self.sync_universe else { return Ok(()) };
+        self.sync_sequence = self.sync_sequence.wrapping_add(1);
+        let packet = build_sync_packet(self.cid, self.sync_sequence, sync_universe);
+        self.send_packet(&packet, sync_universe)
+    }
+
+    fn status(&self) -> OutputStatus {
+        let destination = match self.unicast {
+            Some(ip) => format!("{}:{}", ip, self.port),
+            None => "Multicast 239.255.x.x".to_string(),
+        };
+        OutputStatus {
+            protocol: "sACN",
+            destination,
+            packets_sent: self.packets_sent,
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+pub struct DataPacket<'a> {
+    pub cid: [u8; 16],
+    pub source_name: &'a str,
+    pub priority: u8,
+    /// 0 = ohne Synchronisation.
+    pub sync_universe: u16,
+    pub sequence: u8,
+    pub universe: u16,
+    pub data: &'a [u8; DMX_CHANNELS],
+}
+
+/// Baut ein E1.31 Data Packet aus Root-, Framing- und DMP-Layer.
+pub fn build_data_packet(packet_info: &DataPacket) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(DATA_PACKET_LENGTH);
+
+    // Root Layer
+    write_root_layer(&mut packet, DATA_PACKET_LENGTH, VECTOR_ROOT_E131_DATA, packet_info.cid);
+
+    // Framing Layer
+    write_flags_and_length(&mut packet, DATA_PACKET_LENGTH - 38);
+    packet.extend_from_slice(&VECTOR_E131_DATA_PACKET.to_be_bytes());
+    let name = &packet_info.source_name.as_bytes()[..packet_info.source_name.len().min(SOURCE_NAME_LENGTH - 1)];
+    packet.extend_from_slice(name);
+    packet.resize(packet.len() + SOURCE_NAME_LENGTH - name.len(), 0);
+    packet.push(packet_info.priority.min(MAX_PRIORITY));
+    packet.extend_from_slice(&packet_info.sync_universe.to_be_bytes());
+    packet.push(packet_info.sequence);
+    packet.push(0); // Options
+    packet.extend_from_slice(&packet_info.universe.to_be_bytes());
+
+    // DMP Layer
+    write_flags_and_length(&mut packet, DATA_PACKET_LENGTH - 115);
+    packet.push(VECTOR_DMP_SET_PROPERTY);
+    packet.push(0xA1); // Address Type & Data Type
+    packet.extend_from_slice(&0u16.to_be_bytes()); // First Property Address
+    packet.extend_from_slice(&1u16.to_be_bytes()); // Address Increment
+    packet.extend_from_slice(&(DMX_CHANNELS as u16 + 1).to_be_bytes());
+    packet.push(0); // DMX Start Code
+    packet.extend_from_slice(packet_info.data);
+
+    packet
+}
+
+/// Baut ein E1.31 Synchronization Packet für `sync_universe`.
+pub fn build_sync_packet(cid: [u8; 16], sequence: u8, sync_universe: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(SYNC_PACKET_LENGTH);
+    write_root_layer(&mut packet, SYNC_PACKET_LENGTH, VECTOR_ROOT_E131_EXTENDED, cid);
+    write_flags_and_length(&mut packet, SYNC_PACKET_LENGTH - 38);
+    packet.extend_from_slice(&VECTOR_E131_EXTENDED_SYNCHRONIZATION.to_be_bytes());
+    packet.push(sequence);
+    packet.extend_from_slice(&sync_universe.to_be_bytes());
+    packet.extend_from_slice(&[0, 0]); // Reserved
+    packet
+}
+
+fn write_root_layer(packet: &mut Vec<u8>, total_length: usize, vector: u32, cid: [u8; 16]) {
+    packet.extend_from_slice(&0x0010u16.to_be_bytes()); // Preamble Size
+    packet.extend_from_slice(&0x0000u16.to_be_bytes()); // Postamble Size
+    packet.extend_from_slice(ACN_PACKET_IDENTIFIER);
+    write_flags_and_length(packet, total_length - 16);
+    packet.extend_from_slice(&vector.to_be_bytes());
+    packet.extend_from_slice(&cid);
+}
+
+/// Die oberen 4 Bit sind immer 0x7, die unteren 12 Bit die Länge ab diesem Feld.
+fn write_flags_and_length(packet: &mut Vec<u8>, length: usize) {
+    packet.extend_from_slice(&(0x7000 | (length as u16 & 0x0FFF)).to_be_bytes());
+}
+
+/// Zufällige UUID (Version 4) als CID, ohne extra Abhängigkeit.
+pub fn random_cid() -> [u8; 16] {
+    let mut cid = [0u8; 16];
+    for (i, chunk) in cid.chunks_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(i);
+        hasher.write_u32(std::process::id());
+        if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            hasher.write_u128(time.as_nanos());
+        }
+        chunk.copy_from_slice(&hasher.finish().to_be_bytes());
+    }
+    cid[6] = (cid[6] & 0x0F) | 0x40;
+    cid[8] = (cid[8] & 0x3F) | 0x80;
+    cid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const CID: [u8; 16] = [0xAB; 16];
+
+    fn word(packet: &[u8], offset: usize) -> u16 {
+        u16::from_be_bytes([packet[offset], packet[offset + 1]])
+    }
+
+    #[test]
+    fn data_packet_layout() {
+        let mut data = [0u8; DMX_CHANNELS];
+        data[0] = 11;
+        data[DMX_CHANNELS - 1] = 22;
+        let packet = build_data_packet(&DataPacket {
+            cid: CID,
+            source_name: "rustLamp",
+            priority: 250,
+            sync_universe: 7,
+            sequence: 42,
+            universe: 0x0102,
+            data: &data,
+        });
+
+        assert_eq!(packet.len(), 638);
+        assert_eq!(&packet[4..16], ACN_PACKET_IDENTIFIER);
+        // Flags und Länge von Root-, Framing- und DMP-Layer
+        assert_eq!(word(&packet, 16), 0x726E);
+        assert_eq!(word(&packet, 38), 0x7258);
+        assert_eq!(word(&packet, 115), 0x720B);
+        assert_eq!(&packet[22..38], &CID);
+        assert_eq!(&packet[44..52], b"rustLamp");
+        assert_eq!(packet[52], 0);
+        assert_eq!(packet[108], MAX_PRIORITY);
+        assert_eq!(word(&packet, 109), 7);
+        assert_eq!(packet[111], 42);
+        assert_eq!(word(&packet, 113), 0x0102);
+        assert_eq!(word(&packet, 123), 513);
+        assert_eq!(packet[125], 0);
+        assert_eq!(packet[126], 11);
+        assert_eq!(packet[637], 22);
+    }
+
+    #[test]
+    fn long_source_names_keep_a_terminator() {
+        let name = "x".repeat(100);
+        let packet = build_data_packet(&DataPacket {
+            cid: CID,
+            source_name: &name,
+            priority: DEFAULT_PRIORITY,
+            sync_universe: 0,
+            sequence: 0,
+            universe: 1,
+            data: &[0; DMX_CHANNELS],
+        });
+        assert_eq!(packet.len(), 638);
+        assert_eq!(packet[44 + 62], b'x');
+        assert_eq!(packet[44 + 63], 0);
+    }
+
+    #[test]
+    fn sync_packet_layout() {
+        let packet = build_sync_packet(CID, 9, 0x0304);
+        assert_eq!(packet.len(), 49);
+        assert_eq!(word(&packet, 16), 0x7021);
+        assert_eq!(u32::from_be_bytes(packet[18..22].try_into().unwrap()), VECTOR_ROOT_E131_EXTENDED);
+        assert_eq!(word(&packet, 38), 0x700B);
+        assert_eq!(u32::from_be_bytes(packet[40..44].try_into().unwrap()), VECTOR_E131_EXTENDED_SYNCHRONIZATION);
+        assert_eq!(packet[44], 9);
+        assert_eq!(word(&packet, 45), 0x0304);
+    }
+
+    #[test]
+    fn multicast_groups() {
+        assert_eq!(multicast_address(1), Ipv4Addr::new(239, 255, 0, 1));
+        assert_eq!(multicast_address(63999), Ipv4Addr::new(239, 255, 249, 255));
+    }
+
+    #[test]
+    fn random_cid_is_uuid_v4() {
+        let cid = random_cid();
+        assert_eq!(cid[6] >> 4, 4);
+        assert_eq!(cid[8] >> 6, 0b10);
+        assert_ne!(cid, random_cid());
+    }
+
+    #[test]
+    fn unicast_round_trip() {
+        let receiver = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let port = receiver.local_addr().unwrap().port();
+
+        let mut sender = SacnSender::new("Test").unwrap();
+        sender.set_cid(CID);
+        sender.set_unicast(Some(Ipv4Addr::LOCALHOST));
+        sender.set_port(port);
+        sender.set_sync_universe(Some(9));
+
+        let mut data = [0u8; DMX_CHANNELS];
+        data[3] = 200;
+        sender.send_universe(5, &data).unwrap();
+        sender.send_universe(5, &data).unwrap();
+        sender.flush().unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let mut receive = || {
+            let (len, _) = receiver.recv_from(&mut buffer).unwrap();
+            buffer[..len].to_vec()
+        };
+        let first = receive();
+        let second = receive();
+        let sync = receive();
+
+        assert_eq!(first.len(), 638);
+        assert_eq!(&first[22..38], &CID);
+        assert_eq!(word(&first, 113), 5);
+        assert_eq!(word(&first, 109), 9);
+        assert_eq!(first[129], 200);
+        assert_eq!(second[111], first[111].wrapping_add(1));
+        assert_eq!(sync.len(), 49);
+        assert_eq!(word(&sync, 45), 9);
+        assert_eq!(sender.status().packets_sent, 3);
+        assert_eq!(sender.status().destination, format!("127.0.0.1:{}", port));
+    }
+
+    #[test]
+    fn multicast_loopback() {
+        let universe = 7;
+        let receiver = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        receiver.join_multicast_v4(&multicast_address(universe), &Ipv4Addr::UNSPECIFIED).unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+
+        let mut sender = SacnSender::new("Test").unwrap();
+        sender.set_port(receiver.local_addr().unwrap().port());
+
+        let mut data = [0u8; DMX_CHANNELS];
+        data[0] = 77;
+        sender.send_universe(universe, &data).unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let (len, _) = receiver.recv_from(&mut buffer).unwrap();
+        assert_eq!(len, 638);
+        assert_eq!(word(&buffer, 113), universe);
+        assert_eq!(buffer[126], 77);
+        assert_eq!(sender.status().destination, "Multicast 239.255.x.x");
+    }
+}
@@ -0,0 +1,76 @@
+//! Rastert das HSV-Dreieck des Farbrads ohne GTK, damit es sich auch im Benchmark messen lässt.
+
+pub type Point = (f64, f64);
+
+/// Bytes pro Pixel in `cairo::Format::ARgb32`.
+pub const BYTES_PER_PIXEL: usize = 4;
+
+/// Ganze Pixel, die das Dreieck abdecken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Ecken des Dreiecks: reiner Farbton, Weiß, Schwarz. Bei `rotation` 0 zeigt der Farbton nach oben.
+pub fn triangle_points(center: Point, radius: f64, rotation: f64) -> [Point; 3] {
+    let step = 2.0 * std::f64::consts::PI / 3.0;
+    [0.0, 1.0, 2.0].map(|j| {
+        let angle = -std::f64::consts::PI / 2.0 + j * step + rotation;
+        (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+    })
+}
+
+pub fn bounding_box(points: [Point; 3]) -> PixelRect {
+    let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min).floor() as i32;
+    let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max).ceil() as i32;
+    let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).floor() as i32;
+    let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil() as i32;
+    PixelRect {
+        x: min_x,
+        y: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    }
+}
+
+/// Malt das Dreieck in `data` (ARGB32 mit Zeilenabstand `stride`), dessen Pixel (0, 0) bei `rect.x`, `rect.y` liegt.
+///
+/// Zwischen den Ecken `hue_rgb`, Weiß und Schwarz wird linear gemischt.
+/// Pixel außerhalb des Dreiecks bleiben unverändert.
+pub fn rasterize_triangle(data: &mut [u8], stride: usize, rect: PixelRect, points: [Point; 3], hue_rgb: (f64, f64, f64)) {
+    let [(x0, y0), (x1, y1), (x2, y2)] = points;
+    let denom = (y1 - y2) * (x0 - x2) + (x2 - x1) * (y0 - y2);
+    if denom.abs() < 1e-12 {
+        return;
+    }
+
+    // Die Gewichte sind linear in x, pro Pixel nach rechts kommt jeweils ein fester Schritt dazu
+    let step_hue = (y1 - y2) / denom;
+    let step_white = (y2 - y0) / denom;
+
+    for py in 0..rect.height {
+        let fy = (rect.y + py) as f64 + 0.5;
+        let fx = rect.x as f64 + 0.5;
+        let mut w_hue = ((y1 - y2) * (fx - x2) + (x2 - x1) * (fy - y2)) / denom;
+        let mut w_white = ((y2 - y0) * (fx - x2) + (x0 - x2) * (fy - y2)) / denom;
+
+        let row = py as usize * stride;
+        for px in 0..rect.width as usize {
+            let w_black = 1.0 - w_hue - w_white;
+            if w_hue >= -1e-6 && w_white >= -1e-6 && w_black >= -1e-6 {
+                // Schwarz trägt nichts bei
+                let r8 = ((w_hue * hue_rgb.0 + w_white) * 255.0).clamp(0.0, 255.0) as u32;
+                let g8 = ((w_hue * hue_rgb.1 + w_white) * 255.0).clamp(0.0, 255.0) as u32;
+                let b8 = ((w_hue * hue_rgb.2 + w_white) * 255.0).clamp(0.0, 255.0) as u32;
+                let pixel = (255u32 << 24) | (r8 << 16) | (g8 << 8) | b8;
+                let idx = row + px * BYTES_PER_PIXEL;
+                data[idx..idx + BYTES_PER_PIXEL].copy_from_slice(&pixel.to_ne_bytes());
+            }
+            w_hue += step_hue;
+            w_white += step_white;
+        }
+    }
+}